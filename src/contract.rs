@@ -1,13 +1,26 @@
 use crate::error::ContractError;
 use crate::execute::{
-    change_fee, claim, deposit_collection_reward, stake, transfer_ownership, unstake, whitelist,
-    withdraw_fee,
+    add_reward_schedule, approve_operator, change_fee, claim, coins_to_string,
+    deposit_collection_reward, distribute_fees, emergency_unstake, freeze_collection, grant_role,
+    migrate_legacy_collections, migrate_legacy_config, migrate_legacy_stakings, migrate_stakings,
+    reallocate_collection_reward, receive_cw20, register_collection, revoke_operator, revoke_role,
+    set_collection_admin, set_collection_unstake_fees, set_emergency, set_fee_distribution,
+    set_fee_recycling, set_listing_fee, set_lock_tiers, set_paused, set_unbonding_duration,
+    set_underfunded_policy, set_unstake_penalty, set_weights, stake, unstake, update_ownership,
+    whitelist, withdraw_collection_reward, withdraw_fee, withdraw_unbonded,
 };
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::query::{get_collections, get_config, get_stakings_by_owner};
-use crate::state::{Config, CONFIG};
-use cosmwasm_std::{coin, entry_point, Deps, DepsMut, Env, MessageInfo, QueryResponse, Response};
-use cw2::set_contract_version;
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use crate::query::{
+    get_collections, get_config, get_fee_distribution, get_operators, get_ownership, get_roles,
+    get_staking, get_stakings_by_owner, get_unbonding, get_unpaid_rewards, get_unstake_fee,
+};
+use crate::state::{Config, OperatorApproval, CONFIG};
+use cosmwasm_std::{entry_point, Deps, DepsMut, Env, MessageInfo, QueryResponse, Response};
+use cw2::{get_contract_version, set_contract_version};
+use semver::Version;
+
+const CONTRACT_NAME: &str = "Injective CW721 Staking";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -17,16 +30,18 @@ pub fn instantiate(
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     let config_state = Config {
-        owner: info.clone().sender.to_string(),
-        unstake_fee: msg.unstake_fee,
-        fee_collected: 0,
+        owner: Some(info.clone().sender.to_string()),
+        unstake_fees: msg.unstake_fees,
+        listing_fee: None,
+        paused: false,
+        emergency: false,
     };
     CONFIG.save(deps.storage, &config_state)?;
-    set_contract_version(deps.storage, "Injective CW721 Staking", "0.0.1")?;
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     Ok(Response::new()
         .add_attribute("method", "instantiate")
-        .add_attribute("contract_owner", config_state.owner)
-        .add_attribute("unstake_fee", config_state.unstake_fee.to_string()))
+        .add_attribute("contract_owner", info.sender.to_string())
+        .add_attribute("unstake_fees", coins_to_string(&config_state.unstake_fees)))
 }
 
 #[allow(unreachable_patterns)]
@@ -38,41 +53,183 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::TransferOwnership { address } => transfer_ownership(deps, env, info, address),
-        ExecuteMsg::ChangeFee { fee } => change_fee(deps, env, info, fee),
+        ExecuteMsg::UpdateOwnership(action) => update_ownership(deps, env, info, action),
+        ExecuteMsg::GrantRole { address, role } => grant_role(deps, env, info, address, role),
+        ExecuteMsg::RevokeRole { address, role } => revoke_role(deps, env, info, address, role),
+        ExecuteMsg::ChangeFee { fees } => change_fee(deps, env, info, fees),
+        ExecuteMsg::SetCollectionUnstakeFees { address, fees } => {
+            set_collection_unstake_fees(deps, env, info, address, fees)
+        }
         ExecuteMsg::WhitelistCollection {
             address,
-            reward,
+            rewards,
             cycle,
             is_whitelisted,
             spots,
+            reward_mode,
         } => whitelist(
             deps,
             env,
             info,
             address,
-            reward,
+            rewards,
             cycle,
             is_whitelisted,
             spots,
+            reward_mode,
+        ),
+        ExecuteMsg::AddRewardSchedule {
+            address,
+            asset,
+            start,
+            end,
+            amount,
+        } => add_reward_schedule(deps, env, info, address, asset, start, end, amount),
+        ExecuteMsg::SetListingFee { fee } => set_listing_fee(deps, env, info, fee),
+        ExecuteMsg::RegisterCollection {
+            address,
+            rewards,
+            cycle,
+            spots,
+            reward_mode,
+        } => register_collection(deps, env, info, address, rewards, cycle, spots, reward_mode),
+        ExecuteMsg::SetCollectionAdmin { address, admin } => {
+            set_collection_admin(deps, env, info, address, admin)
+        }
+        ExecuteMsg::SetUnbondingDuration { address, duration } => {
+            set_unbonding_duration(deps, env, info, address, duration)
+        }
+        ExecuteMsg::SetUnderfundedPolicy { address, policy } => {
+            set_underfunded_policy(deps, env, info, address, policy)
+        }
+        ExecuteMsg::SetUnstakePenalty {
+            address,
+            curve,
+            charge,
+        } => set_unstake_penalty(deps, env, info, address, curve, charge),
+        ExecuteMsg::SetFeeRecycling { address, share } => {
+            set_fee_recycling(deps, env, info, address, share)
+        }
+        ExecuteMsg::SetLockTiers { address, tiers } => {
+            set_lock_tiers(deps, env, info, address, tiers)
+        }
+        ExecuteMsg::SetWeights {
+            address,
+            token_weights,
+            trait_weights,
+            snapshot_on_stake,
+        } => set_weights(
+            deps,
+            env,
+            info,
+            address,
+            token_weights,
+            trait_weights,
+            snapshot_on_stake,
         ),
+        ExecuteMsg::FreezeCollection { address, frozen } => {
+            freeze_collection(deps, env, info, address, frozen)
+        }
         ExecuteMsg::DepositCollectionReward { address } => {
             deposit_collection_reward(deps, env, info, address)
         }
+        ExecuteMsg::WithdrawCollectionReward { address, asset } => {
+            withdraw_collection_reward(deps, env, info, address, asset)
+        }
+        ExecuteMsg::ReallocateCollectionReward { from, to, asset } => {
+            reallocate_collection_reward(deps, env, info, from, to, asset)
+        }
+        ExecuteMsg::SetPaused { paused } => set_paused(deps, env, info, paused),
+        ExecuteMsg::SetEmergency { emergency } => set_emergency(deps, env, info, emergency),
+        ExecuteMsg::EmergencyUnstake {
+            collection,
+            token_id,
+        } => emergency_unstake(deps, env, info, collection, token_id),
+        ExecuteMsg::WithdrawUnbonded { owner, limit } => {
+            withdraw_unbonded(deps, env, info, owner, limit)
+        }
         ExecuteMsg::WithdrawFee { fee } => withdraw_fee(deps, env, info, fee),
+        ExecuteMsg::SetFeeDistribution { shares } => set_fee_distribution(deps, env, info, shares),
+        ExecuteMsg::DistributeFees {} => distribute_fees(deps, env, info),
+        ExecuteMsg::MigrateStakings { limit } => migrate_stakings(deps, env, info, limit),
         ExecuteMsg::ReceiveNft(msg) => stake(deps, env, info, msg),
-        ExecuteMsg::Unstake { index } => unstake(deps, env, info, index),
-        ExecuteMsg::ClaimReward { index } => claim(deps, env, info, index),
+        ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
+        ExecuteMsg::Unstake {
+            collection,
+            token_id,
+            recipient,
+        } => unstake(deps, env, info, collection, token_id, recipient),
+        ExecuteMsg::ClaimReward {
+            collection,
+            token_id,
+            recipient,
+        } => claim(deps, env, info, collection, token_id, recipient),
+        ExecuteMsg::ApproveOperator {
+            operator,
+            claim,
+            unstake,
+            set_recipient,
+            expiry,
+        } => approve_operator(
+            deps,
+            env,
+            info,
+            operator,
+            OperatorApproval {
+                claim,
+                unstake,
+                set_recipient,
+                expiry,
+            },
+        ),
+        ExecuteMsg::RevokeOperator { operator } => revoke_operator(deps, env, info, operator),
         _ => Err(ContractError::Unknown {}),
     }
 }
 
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let previous = get_contract_version(deps.storage)?;
+    if previous.contract != CONTRACT_NAME
+        || Version::parse(&previous.version)? > Version::parse(CONTRACT_VERSION)?
+    {
+        return Err(ContractError::CannotMigrate {
+            previous_contract: previous.contract,
+            previous_version: previous.version,
+        });
+    }
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    let config = migrate_legacy_config(deps.storage)?;
+    let collections = migrate_legacy_collections(deps.storage)?;
+    // remaining legacy stakings can be converted with MigrateStakings
+    let response = migrate_legacy_stakings(deps.storage, env.block.time, msg.limit)?;
+    Ok(response
+        .add_attribute("method", "migrate")
+        .add_attribute("config", config.to_string())
+        .add_attribute("collections", collections.to_string())
+        .add_attribute("previous_version", previous.version)
+        .add_attribute("version", CONTRACT_VERSION))
+}
+
 #[entry_point]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<QueryResponse, ContractError> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<QueryResponse, ContractError> {
     match msg {
         QueryMsg::GetConfig {} => get_config(deps),
-        QueryMsg::GetCollections {} => get_collections(deps, _env),
+        QueryMsg::GetOwnership {} => get_ownership(deps),
+        QueryMsg::GetRoles {} => get_roles(deps),
+        QueryMsg::GetCollections {} => get_collections(deps, env),
         QueryMsg::GetStakingsByOwner { owner } => get_stakings_by_owner(deps, owner),
-        _ => Err(ContractError::Unknown {}),
+        QueryMsg::GetStaking {
+            collection,
+            token_id,
+        } => get_staking(deps, collection, token_id),
+        QueryMsg::GetUnbonding { owner } => get_unbonding(deps, owner),
+        QueryMsg::GetOperators { owner } => get_operators(deps, owner),
+        QueryMsg::GetUnpaidRewards { collection } => get_unpaid_rewards(deps, collection),
+        QueryMsg::GetFeeDistribution {} => get_fee_distribution(deps),
+        QueryMsg::GetUnstakeFee {
+            collection,
+            token_id,
+        } => get_unstake_fee(deps, env, collection, token_id),
     }
 }
@@ -1,4 +1,7 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{
+    CheckedFromRatioError, CheckedMultiplyRatioError, ConversionOverflowError, DivideByZeroError,
+    OverflowError, StdError, Timestamp, Uint128,
+};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,6 +9,21 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Overflow(#[from] OverflowError),
+
+    #[error("{0}")]
+    DivideByZero(#[from] DivideByZeroError),
+
+    #[error("{0}")]
+    CheckedMultiplyRatio(#[from] CheckedMultiplyRatioError),
+
+    #[error("{0}")]
+    CheckedFromRatio(#[from] CheckedFromRatioError),
+
+    #[error("{0}")]
+    ConversionOverflow(#[from] ConversionOverflowError),
+
     #[error("Not whitelisted collection")]
     NotWhitelisted {},
 
@@ -35,4 +53,109 @@ pub enum ContractError {
 
     #[error("Not enough unstake fee")]
     NotEnoughUnstakeFee {},
+
+    #[error("Invalid reward cycle")]
+    InvalidCycle {},
+
+    #[error("No staking spots available")]
+    NoSpotsAvailable {},
+
+    #[error("Collection is frozen")]
+    CollectionFrozen {},
+
+    #[error("Token is not staked")]
+    NotStaked {},
+
+    #[error("Token is already staked")]
+    AlreadyStaked {},
+
+    #[error("Legacy stakings are still being migrated")]
+    MigrationPending {},
+
+    #[error("Cannot migrate from {previous_contract} {previous_version}")]
+    CannotMigrate {
+        previous_contract: String,
+        previous_version: String,
+    },
+
+    #[error("Invalid lock tier")]
+    InvalidLockTier {},
+
+    #[error("Trait weights require weight snapshots on stake")]
+    TraitWeightsNeedSnapshot {},
+
+    #[error("Locked up until {unlock_timestamp}")]
+    LockedUp { unlock_timestamp: Timestamp },
+
+    #[error("Duplicate reward asset {asset}")]
+    DuplicateRewardAsset { asset: String },
+
+    #[error("Unknown reward asset {asset}")]
+    UnknownRewardAsset { asset: String },
+
+    #[error("Shared reward mode can not be switched while tokens are staked")]
+    RewardModeLocked {},
+
+    #[error("Invalid reward schedule")]
+    InvalidSchedule {},
+
+    #[error("Invalid unstake penalty")]
+    InvalidPenalty {},
+
+    #[error("Fee shares must be positive and sum up to 10000 bps")]
+    InvalidFeeShares {},
+
+    #[error("No fee distribution is set")]
+    NoFeeDistribution {},
+
+    #[error("Fee recycle share can not exceed 100%")]
+    InvalidRecycleShare {},
+
+    #[error("Only {available} of the reward pool is not owed to stakers")]
+    PoolFundsAllocated { available: Uint128 },
+
+    #[error("Collection registration is closed")]
+    RegistrationClosed {},
+
+    #[error("Not enough listing fee")]
+    NotEnoughListingFee {},
+
+    #[error("Collection already exists")]
+    CollectionExists {},
+
+    #[error("Not a cw721 contract")]
+    NotCw721 {},
+
+    #[error("No pending ownership transfer")]
+    NoPendingOwnership {},
+
+    #[error("Ownership transfer expired")]
+    OwnershipTransferExpired {},
+
+    #[error("Operator approval expired")]
+    ApprovalExpired {},
+
+    #[error("Contract is paused")]
+    Paused {},
+
+    #[error("Emergency mode is off")]
+    NotEmergency {},
+
+    #[error("Unbonding duration can not exceed {max} seconds")]
+    InvalidUnbondingDuration { max: u64 },
+
+    #[error("No unbonded nft to withdraw")]
+    NothingToWithdraw {},
+
+    #[error("No funds sent")]
+    NoFunds {},
+
+    #[error("Semver parsing error: {0}")]
+    SemVer(String),
+}
+
+impl From<semver::Error> for ContractError {
+    fn from(err: semver::Error) -> Self {
+        Self::SemVer(err.to_string())
+    }
 }
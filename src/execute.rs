@@ -1,334 +1,2613 @@
-use std::str::FromStr;
-use std::sync::Arc;
 use std::vec;
 
 use crate::error::ContractError;
 
-use crate::msg::UniversalNftReceiveMsg;
-use crate::state::{Collection, Staking, COLLECTIONS, CONFIG, STAKINGS};
+use crate::msg::{
+    OwnershipAction, ReceiveMsg, StakeMsg, TokenWeight, TraitNftInfoResponse, TraitWeight,
+    UniversalNftReceiveMsg,
+};
+use crate::state::{
+    checked_plus_seconds, stakings, Asset, AssetInfo, Collection, Config, FeeRecipient, FeeShare,
+    FrozenPeriod, LegacyStaking, LockTier, OperatorApproval, PenaltyCharge, PenaltyCurve,
+    PendingOwnership, RewardDebt, RewardMode, RewardSchedule, RewardStream, Role, Staking,
+    UnderfundedPolicy, COLLECTIONS, CONFIG, FEES_COLLECTED, FEE_DISTRIBUTION, LEGACY_COLLECTIONS,
+    LEGACY_CONFIG, LEGACY_STAKINGS, OPERATORS, PENDING_OWNERSHIP, ROLES, TOKEN_WEIGHTS,
+    TRAIT_WEIGHTS, UNPAID_REWARDS,
+};
 use cosmwasm_std::{
-    coin, to_json_binary, BankMsg, Coin, CosmosMsg, DepsMut, Env, Event, MessageInfo, Response,
-    StdResult, Storage, Timestamp, Uint128, WasmMsg,
+    from_json, to_json_binary, BankMsg, Coin, CosmosMsg, Decimal, Decimal256, Deps, DepsMut, Env,
+    Event, MessageInfo, Order, Response, StdError, StdResult, Storage, Timestamp, Uint128, WasmMsg,
 };
-use cw721::{Cw721ExecuteMsg, Cw721ReceiveMsg};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use cw721::{
+    ContractInfoResponse as Cw721ContractInfoResponse, Cw721ExecuteMsg, Cw721QueryMsg,
+    NumTokensResponse,
+};
+use cw_storage_plus::Bound;
+
+pub fn update_ownership(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    action: OwnershipAction,
+) -> Result<Response, ContractError> {
+    let sender = info.sender.to_string();
+    match action {
+        OwnershipAction::TransferOwnership { new_owner, expiry } => {
+            let new_owner = deps.api.addr_validate(&new_owner)?.to_string();
+            let store = deps.branch().storage;
+            check_contract_owner_only(info.clone(), store)?;
+            if let Some(expiry) = &expiry {
+                if expiry.is_expired(&env.block) {
+                    return Err(ContractError::OwnershipTransferExpired {});
+                }
+            }
+            PENDING_OWNERSHIP.save(
+                store,
+                &PendingOwnership {
+                    new_owner: new_owner.clone(),
+                    expiry,
+                },
+            )?;
+            Ok(Response::new().add_event(
+                Event::new("ownership_transfer_proposed")
+                    .add_attribute("owner", sender)
+                    .add_attribute("pending_owner", new_owner),
+            ))
+        }
+        OwnershipAction::AcceptOwnership => {
+            let store = deps.branch().storage;
+            let pending = PENDING_OWNERSHIP
+                .may_load(store)?
+                .ok_or(ContractError::NoPendingOwnership {})?;
+            if pending.new_owner != sender {
+                return Err(ContractError::Unauthorized {});
+            }
+            if let Some(expiry) = &pending.expiry {
+                if expiry.is_expired(&env.block) {
+                    return Err(ContractError::OwnershipTransferExpired {});
+                }
+            }
+            let mut config_state = CONFIG.load(store)?;
+            let old_owner = config_state.owner.unwrap_or_default();
+            config_state.owner = Some(pending.new_owner.clone());
+            CONFIG.save(store, &config_state)?;
+            PENDING_OWNERSHIP.remove(store);
+            Ok(Response::new().add_event(
+                Event::new("ownership_transferred")
+                    .add_attribute("old_owner", old_owner)
+                    .add_attribute("new_owner", pending.new_owner),
+            ))
+        }
+        OwnershipAction::CancelOwnershipTransfer => {
+            let store = deps.branch().storage;
+            check_contract_owner_only(info.clone(), store)?;
+            let pending = PENDING_OWNERSHIP
+                .may_load(store)?
+                .ok_or(ContractError::NoPendingOwnership {})?;
+            PENDING_OWNERSHIP.remove(store);
+            Ok(Response::new().add_event(
+                Event::new("ownership_transfer_cancelled")
+                    .add_attribute("owner", sender)
+                    .add_attribute("pending_owner", pending.new_owner),
+            ))
+        }
+        OwnershipAction::RenounceOwnership => {
+            let store = deps.branch().storage;
+            check_contract_owner_only(info.clone(), store)?;
+            let mut config_state = CONFIG.load(store)?;
+            config_state.owner = None;
+            CONFIG.save(store, &config_state)?;
+            PENDING_OWNERSHIP.remove(store);
+            Ok(Response::new()
+                .add_event(Event::new("ownership_renounced").add_attribute("old_owner", sender)))
+        }
+    }
+}
+
+pub fn approve_operator(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    operator: String,
+    approval: OperatorApproval,
+) -> Result<Response, ContractError> {
+    let operator = deps.api.addr_validate(&operator)?.to_string();
+    let store = deps.branch().storage;
+    if let Some(expiry) = &approval.expiry {
+        if expiry.is_expired(&env.block) {
+            return Err(ContractError::ApprovalExpired {});
+        }
+    }
+    OPERATORS.save(
+        store,
+        (info.sender.to_string(), operator.clone()),
+        &approval,
+    )?;
+    Ok(Response::new().add_event(
+        Event::new("operator_approved")
+            .add_attribute("owner", info.sender.to_string())
+            .add_attribute("operator", operator)
+            .add_attribute("claim", approval.claim.to_string())
+            .add_attribute("unstake", approval.unstake.to_string())
+            .add_attribute("set_recipient", approval.set_recipient.to_string()),
+    ))
+}
+
+pub fn revoke_operator(
+    mut deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    operator: String,
+) -> Result<Response, ContractError> {
+    let store = deps.branch().storage;
+    OPERATORS.remove(store, (info.sender.to_string(), operator.clone()));
+    Ok(Response::new().add_event(
+        Event::new("operator_revoked")
+            .add_attribute("owner", info.sender.to_string())
+            .add_attribute("operator", operator),
+    ))
+}
 
-pub fn transfer_ownership(
+pub fn grant_role(
     mut deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    new_owner: String,
+    address: String,
+    role: Role,
 ) -> Result<Response, ContractError> {
+    let address = deps.api.addr_validate(&address)?.to_string();
     let store = deps.branch().storage;
     check_contract_owner_only(info.clone(), store)?;
-    let mut config_state = CONFIG.load(store).unwrap();
-    let old_owner = config_state.owner;
-    config_state.owner = new_owner.clone();
-    CONFIG.save(deps.storage, &config_state)?;
+    let mut roles = ROLES.may_load(store, address.clone())?.unwrap_or_default();
+    if !roles.contains(&role) {
+        roles.push(role.clone());
+    }
+    ROLES.save(store, address.clone(), &roles)?;
     Ok(Response::new().add_event(
-        Event::new("ownership_transferred")
-            .add_attribute("old_owner", old_owner)
-            .add_attribute("new_owner", new_owner),
+        Event::new("role_granted")
+            .add_attribute("address", address)
+            .add_attribute("role", format!("{:?}", role)),
     ))
 }
 
-pub fn change_fee(
+pub fn revoke_role(
     mut deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    new_fee: Coin,
+    address: String,
+    role: Role,
 ) -> Result<Response, ContractError> {
     let store = deps.branch().storage;
     check_contract_owner_only(info.clone(), store)?;
-    let mut config_state = CONFIG.load(store).unwrap();
-    let old_fee = config_state.unstake_fee;
-    config_state.unstake_fee = new_fee.clone();
-    CONFIG.save(deps.storage, &config_state)?;
+    let mut roles = ROLES.may_load(store, address.clone())?.unwrap_or_default();
+    roles.retain(|r| r != &role);
+    if roles.is_empty() {
+        ROLES.remove(store, address.clone());
+    } else {
+        ROLES.save(store, address.clone(), &roles)?;
+    }
+    Ok(Response::new().add_event(
+        Event::new("role_revoked")
+            .add_attribute("address", address)
+            .add_attribute("role", format!("{:?}", role)),
+    ))
+}
+
+pub fn change_fee(
+    mut deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    new_fees: Vec<Coin>,
+) -> Result<Response, ContractError> {
+    let store = deps.branch().storage;
+    check_role(info.clone(), store, Role::FeeManager)?;
+    let mut config_state = CONFIG.load(store)?;
+    let old_fees = config_state.unstake_fees;
+    config_state.unstake_fees = new_fees.clone();
+    CONFIG.save(store, &config_state)?;
     Ok(Response::new().add_event(
         Event::new("fee_changed")
-            .add_attribute("old_fee", old_fee.to_string())
-            .add_attribute("new_fee", new_fee.to_string()),
+            .add_attribute("old_fee", coins_to_string(&old_fees))
+            .add_attribute("new_fee", coins_to_string(&new_fees)),
     ))
 }
 
-pub fn whitelist(
+pub fn set_collection_unstake_fees(
     mut deps: DepsMut,
     _env: Env,
     info: MessageInfo,
     address: String,
-    reward: Coin,
+    fees: Option<Vec<Coin>>,
+) -> Result<Response, ContractError> {
+    let store = deps.branch().storage;
+    check_role(info.clone(), store, Role::FeeManager)?;
+    let mut collection = COLLECTIONS
+        .may_load(store, address.clone())?
+        .ok_or(ContractError::NotWhitelisted {})?;
+    collection.unstake_fees = fees.clone();
+    COLLECTIONS.save(store, address.clone(), &collection)?;
+    Ok(Response::new().add_event(
+        Event::new("collection_fee_changed")
+            .add_attribute("address", address)
+            .add_attribute(
+                "fee",
+                fees.map_or("default".to_string(), |fees| coins_to_string(&fees)),
+            ),
+    ))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn whitelist(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: String,
+    rewards: Vec<Asset>,
     cycle: u64,
     is_whitelisted: bool,
     spots: u64,
+    reward_mode: Option<RewardMode>,
 ) -> Result<Response, ContractError> {
     let store = deps.branch().storage;
-    check_contract_owner_only(info.clone(), store)?;
+    validate_rewards(&rewards, cycle)?;
     let collection = COLLECTIONS.may_load(store, address.clone())?;
-    if collection.is_none() {
-        let new_collection = Collection::new(reward.clone(), cycle.clone(), true, spots, 0);
-        COLLECTIONS.save(store, address.clone(), &new_collection)?;
-    } else {
-        COLLECTIONS.update(store, address.clone(), |c| -> StdResult<Collection> {
-            Ok(Collection::new(
-                reward.clone(),
+    let reward_mode = match collection {
+        None => {
+            check_role(info.clone(), store, Role::CollectionManager)?;
+            let reward_mode = reward_mode.unwrap_or_default();
+            let new_collection = Collection::new(
+                reward_streams(&rewards, env.block.time, cycle),
                 cycle,
-                is_whitelisted,
+                true,
                 spots,
-                c.clone().unwrap().pool_amount,
-            ))
-        })?;
-    }
+                reward_mode.clone(),
+            );
+            COLLECTIONS.save(store, address.clone(), &new_collection)?;
+            reward_mode
+        }
+        Some(mut col) => {
+            check_collection_admin(info.clone(), store, &col)?;
+            // listing and delisting is left to the contract owner
+            if is_whitelisted != col.is_whitelisted {
+                check_role(info.clone(), store, Role::CollectionManager)?;
+            }
+            // new rates apply from now on, streams left out stop emitting but keep their pool.
+            // time bounded schedules are left untouched.
+            for stream in col.rewards.iter_mut() {
+                stream.close_open_schedule(env.block.time);
+            }
+            for reward in rewards.iter() {
+                if !col.rewards.iter().any(|stream| stream.asset == reward.info) {
+                    col.rewards.push(RewardStream {
+                        asset: reward.info.clone(),
+                        schedules: vec![],
+                        pool_amount: Uint128::zero(),
+                        reward_per_share: Decimal256::zero(),
+                        debt_amount: Uint128::zero(),
+                        accrued_amount: Decimal256::zero(),
+                    });
+                }
+                let stream = col
+                    .rewards
+                    .iter_mut()
+                    .find(|stream| stream.asset == reward.info)
+                    .unwrap();
+                if !reward.amount.is_zero() {
+                    stream.schedules.push(RewardSchedule {
+                        start: env.block.time,
+                        end: None,
+                        amount: reward.amount,
+                        cycle,
+                    });
+                }
+            }
+            col.cycle = cycle;
+            col.is_whitelisted = is_whitelisted;
+            col.spots = spots;
+            if let Some(reward_mode) = reward_mode {
+                // shares accounting only starts or stops on an empty collection
+                let shared = RewardMode::Shared;
+                if (col.reward_mode == shared) != (reward_mode == shared) && col.staked > 0 {
+                    return Err(ContractError::RewardModeLocked {});
+                }
+                col.reward_mode = reward_mode;
+            }
+            COLLECTIONS.save(store, address.clone(), &col)?;
+            col.reward_mode
+        }
+    };
     Ok(Response::new().add_event(
         Event::new("collection_whitelisted")
             .add_attribute("address", address)
-            .add_attribute("reward", reward.to_string())
+            .add_attribute("rewards", assets_to_string(&rewards))
             .add_attribute("cycle", cycle.to_string())
             .add_attribute("is_whitelisted", is_whitelisted.to_string())
-            .add_attribute("spots", spots.to_string()),
+            .add_attribute("spots", spots.to_string())
+            .add_attribute("reward_mode", format!("{:?}", reward_mode)),
     ))
 }
 
-pub fn deposit_collection_reward(
+#[allow(clippy::too_many_arguments)]
+pub fn register_collection(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: String,
+    rewards: Vec<Asset>,
+    cycle: u64,
+    spots: u64,
+    reward_mode: Option<RewardMode>,
+) -> Result<Response, ContractError> {
+    list_collection(
+        deps,
+        env,
+        info.sender.to_string(),
+        &info.funds,
+        None,
+        address,
+        rewards,
+        cycle,
+        spots,
+        reward_mode,
+    )
+}
+
+// list a cw721 created by `sender` as its admin. the funds pay the listing fee, what is
+// left and the cw20 deposit make the initial reward pool.
+#[allow(clippy::too_many_arguments)]
+fn list_collection(
     mut deps: DepsMut,
-    _env: Env,
+    env: Env,
+    sender: String,
+    funds: &[Coin],
+    cw20_deposit: Option<Asset>,
+    address: String,
+    rewards: Vec<Asset>,
+    cycle: u64,
+    spots: u64,
+    reward_mode: Option<RewardMode>,
+) -> Result<Response, ContractError> {
+    let address = deps.api.addr_validate(&address)?.to_string();
+    // only the creator or wasm admin of a contract can list it
+    let contract_info = deps.querier.query_wasm_contract_info(address.clone())?;
+    if contract_info.creator != sender && contract_info.admin != Some(sender.clone()) {
+        return Err(ContractError::Unauthorized {});
+    }
+    deps.querier
+        .query_wasm_smart::<Cw721ContractInfoResponse>(
+            address.clone(),
+            &Cw721QueryMsg::ContractInfo {},
+        )
+        .map_err(|_| ContractError::NotCw721 {})?;
+    deps.querier
+        .query_wasm_smart::<NumTokensResponse>(address.clone(), &Cw721QueryMsg::NumTokens {})
+        .map_err(|_| ContractError::NotCw721 {})?;
+    let store = deps.branch().storage;
+    check_not_paused(store)?;
+    let config_state = CONFIG.load(store)?;
+    let listing_fee = config_state
+        .listing_fee
+        .clone()
+        .ok_or(ContractError::RegistrationClosed {})?;
+    if COLLECTIONS.has(store, address.clone()) {
+        return Err(ContractError::CollectionExists {});
+    }
+    validate_rewards(&rewards, cycle)?;
+    // funds left after the listing fee make the initial reward pool
+    let mut fee_paid = listing_fee.amount.is_zero();
+    let mut deposits: Vec<Asset> = vec![];
+    for fund in funds.iter() {
+        let mut amount = fund.amount;
+        if !fee_paid && fund.denom == listing_fee.denom {
+            amount = amount
+                .checked_sub(listing_fee.amount)
+                .map_err(|_| ContractError::NotEnoughListingFee {})?;
+            fee_paid = true;
+        }
+        if !amount.is_zero() {
+            deposits.push(Asset {
+                info: AssetInfo::Native {
+                    denom: fund.denom.clone(),
+                },
+                amount,
+            });
+        }
+    }
+    // native coins can not come with a cw20 deposit, it is only accepted for free listings
+    if !fee_paid {
+        return Err(ContractError::NotEnoughListingFee {});
+    }
+    deposits.extend(cw20_deposit);
+    // a listed collection must come with an initial reward pool
+    if deposits.is_empty() {
+        return Err(ContractError::NoFunds {});
+    }
+    let mut collection = Collection::new(
+        reward_streams(&rewards, env.block.time, cycle),
+        cycle,
+        true,
+        spots,
+        reward_mode.unwrap_or_default(),
+    );
+    collection.admin = Some(sender.clone());
+    for deposit in deposits.iter() {
+        credit_reward_pool(&mut collection, deposit)?;
+    }
+    COLLECTIONS.save(store, address.clone(), &collection)?;
+    if !listing_fee.amount.is_zero() {
+        add_collected_fee(store, &listing_fee)?;
+    }
+    Ok(Response::new().add_event(
+        Event::new("collection_registered")
+            .add_attribute("address", address)
+            .add_attribute("admin", sender)
+            .add_attribute("rewards", assets_to_string(&rewards))
+            .add_attribute("cycle", cycle.to_string())
+            .add_attribute("spots", spots.to_string())
+            .add_attribute("listing_fee", listing_fee.to_string())
+            .add_attribute("deposit", assets_to_string(&deposits)),
+    ))
+}
+
+fn validate_rewards(rewards: &[Asset], cycle: u64) -> Result<(), ContractError> {
+    if cycle == 0 {
+        return Err(ContractError::InvalidCycle {});
+    }
+    for (i, reward) in rewards.iter().enumerate() {
+        if rewards[..i].iter().any(|r| r.info == reward.info) {
+            return Err(ContractError::DuplicateRewardAsset {
+                asset: reward.info.to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
+// reward streams of a new collection, emitting from `start`.
+fn reward_streams(rewards: &[Asset], start: Timestamp, cycle: u64) -> Vec<RewardStream> {
+    rewards
+        .iter()
+        .map(|reward| RewardStream {
+            asset: reward.info.clone(),
+            schedules: vec![RewardSchedule {
+                start,
+                end: None,
+                amount: reward.amount,
+                cycle,
+            }],
+            pool_amount: Uint128::zero(),
+            reward_per_share: Decimal256::zero(),
+            debt_amount: Uint128::zero(),
+            accrued_amount: Decimal256::zero(),
+        })
+        .collect()
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn add_reward_schedule(
+    mut deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     address: String,
+    asset: AssetInfo,
+    start: Timestamp,
+    end: Timestamp,
+    amount: Uint128,
 ) -> Result<Response, ContractError> {
     let store = deps.branch().storage;
-    let collection = COLLECTIONS.may_load(store, address.clone())?;
-    let index = info.funds.iter().position(|r| r.denom == "inj").unwrap();
-    let amount: u128 = info.funds[index].amount.into();
-    if collection.is_none() {
-        return Err(ContractError::Unknown {});
-    } else {
-        COLLECTIONS.update(store, address.clone(), |c| -> StdResult<Collection> {
-            let col = c.clone().unwrap();
-            Ok(Collection::new(
-                col.reward,
-                col.cycle,
-                col.is_whitelisted,
-                col.spots,
-                col.pool_amount + amount,
-            ))
-        })?;
+    if start < env.block.time || end <= start {
+        return Err(ContractError::InvalidSchedule {});
+    }
+    let mut collection = COLLECTIONS
+        .may_load(store, address.clone())?
+        .ok_or(ContractError::NotWhitelisted {})?;
+    check_collection_admin(info.clone(), store, &collection)?;
+    if !collection
+        .rewards
+        .iter()
+        .any(|stream| stream.asset == asset)
+    {
+        collection.rewards.push(RewardStream {
+            asset: asset.clone(),
+            schedules: vec![],
+            pool_amount: Uint128::zero(),
+            reward_per_share: Decimal256::zero(),
+            debt_amount: Uint128::zero(),
+            accrued_amount: Decimal256::zero(),
+        });
     }
+    let cycle = collection.cycle;
+    let stream = collection
+        .rewards
+        .iter_mut()
+        .find(|stream| stream.asset == asset)
+        .unwrap();
+    stream.schedules.push(RewardSchedule {
+        start,
+        end: Some(end),
+        amount,
+        cycle,
+    });
+    COLLECTIONS.save(store, address.clone(), &collection)?;
     Ok(Response::new().add_event(
-        Event::new("collection_reward_deposited")
+        Event::new("reward_schedule_added")
             .add_attribute("address", address)
-            .add_attribute("amount", amount.to_string()),
+            .add_attribute("asset", asset.to_string())
+            .add_attribute("start", start.seconds().to_string())
+            .add_attribute("end", end.seconds().to_string())
+            .add_attribute("amount", amount.to_string())
+            .add_attribute("cycle", cycle.to_string()),
     ))
 }
-pub fn withdraw_fee(
+
+pub fn set_collection_admin(
     mut deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    fee: Coin,
+    address: String,
+    admin: Option<String>,
 ) -> Result<Response, ContractError> {
+    let admin = match admin {
+        Some(admin) => Some(deps.api.addr_validate(&admin)?.to_string()),
+        None => None,
+    };
     let store = deps.branch().storage;
-    check_contract_owner_only(info.clone(), store)?;
-    let mut config_state = CONFIG.load(store).unwrap();
-    let fee_amount = u128::from_str(&fee.clone().amount.to_string()).unwrap();
-    if fee_amount > config_state.fee_collected.clone() {
-        return Err(ContractError::NotEnoughFeeCollected {});
+    let mut collection = COLLECTIONS
+        .may_load(store, address.clone())?
+        .ok_or(ContractError::NotWhitelisted {})?;
+    check_collection_admin(info.clone(), store, &collection)?;
+    collection.admin = admin.clone();
+    COLLECTIONS.save(store, address.clone(), &collection)?;
+    Ok(Response::new().add_event(
+        Event::new("collection_admin_set")
+            .add_attribute("address", address)
+            .add_attribute("admin", admin.unwrap_or("none".to_string())),
+    ))
+}
+
+const MAX_UNBONDING_DURATION: u64 = 7_776_000; // 90 days
+
+// unbonding duration of stakings made from now on, existing stakings keep theirs.
+pub fn set_unbonding_duration(
+    mut deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    address: String,
+    duration: u64,
+) -> Result<Response, ContractError> {
+    let store = deps.branch().storage;
+    let mut collection = COLLECTIONS
+        .may_load(store, address.clone())?
+        .ok_or(ContractError::NotWhitelisted {})?;
+    check_collection_admin(info.clone(), store, &collection)?;
+    if duration > MAX_UNBONDING_DURATION {
+        return Err(ContractError::InvalidUnbondingDuration {
+            max: MAX_UNBONDING_DURATION,
+        });
     }
-    config_state.fee_collected = config_state.fee_collected.clone() - fee_amount;
-    CONFIG.save(deps.storage, &config_state)?;
+    collection.unbonding_duration = duration;
+    COLLECTIONS.save(store, address.clone(), &collection)?;
+    Ok(Response::new().add_event(
+        Event::new("unbonding_duration_set")
+            .add_attribute("address", address)
+            .add_attribute("duration", duration.to_string()),
+    ))
+}
 
-    let transfer_msg = BankMsg::Send {
-        to_address: info.sender.to_string(),
-        amount: vec![fee.clone()],
-    };
-    Ok(Response::new()
-        .add_event(
-            Event::new("fee_withdrawn")
-                .add_attribute("address", info.sender.to_string())
-                .add_attribute("fee", fee.to_string()),
-        )
-        .add_message(transfer_msg))
+pub fn set_underfunded_policy(
+    mut deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    address: String,
+    policy: UnderfundedPolicy,
+) -> Result<Response, ContractError> {
+    let store = deps.branch().storage;
+    let mut collection = COLLECTIONS
+        .may_load(store, address.clone())?
+        .ok_or(ContractError::NotWhitelisted {})?;
+    check_collection_admin(info.clone(), store, &collection)?;
+    collection.underfunded_policy = policy.clone();
+    COLLECTIONS.save(store, address.clone(), &collection)?;
+    Ok(Response::new().add_event(
+        Event::new("underfunded_policy_set")
+            .add_attribute("address", address)
+            .add_attribute("policy", format!("{:?}", policy)),
+    ))
 }
 
-pub fn stake(
+pub fn set_unstake_penalty(
     mut deps: DepsMut,
-    env: Env,
+    _env: Env,
     info: MessageInfo,
-    msg: UniversalNftReceiveMsg,
+    address: String,
+    curve: PenaltyCurve,
+    charge: PenaltyCharge,
 ) -> Result<Response, ContractError> {
-    let token_address = info.sender.to_string();
-    let owner = msg.clone().sender;
     let store = deps.branch().storage;
-    let collection = COLLECTIONS.may_load(store, token_address.clone())?;
-    if collection.is_none() {
-        return Err(ContractError::NotWhitelisted {});
+    check_role(info.clone(), store, Role::FeeManager)?;
+    let mut collection = COLLECTIONS
+        .may_load(store, address.clone())?
+        .ok_or(ContractError::NotWhitelisted {})?;
+    // steps must be ordered by elapsed time and no share may exceed the full fee
+    if let PenaltyCurve::Stepwise { steps } = &curve {
+        if steps.iter().any(|step| step.percent > Decimal::one())
+            || steps
+                .windows(2)
+                .any(|pair| pair[0].elapsed >= pair[1].elapsed)
+        {
+            return Err(ContractError::InvalidPenalty {});
+        }
     }
-    let stakings = STAKINGS.may_load(store, owner.clone())?;
-    let mut stakings_state: Vec<Staking>;
-    if stakings.is_none() {
-        stakings_state = vec![]
-    } else {
-        stakings_state = stakings.unwrap()
-    };
-    stakings_state.push(Staking::new(
-        token_address.clone(),
-        msg.clone().token_id,
-        env.block.time,
-        false,
-    ));
-    STAKINGS.save(store, owner.clone(), &stakings_state)?;
+    if let PenaltyCharge::RewardShare { percent } = &charge {
+        if *percent > Decimal::one() {
+            return Err(ContractError::InvalidPenalty {});
+        }
+    }
+    collection.penalty_curve = curve.clone();
+    collection.penalty_charge = charge.clone();
+    COLLECTIONS.save(store, address.clone(), &collection)?;
     Ok(Response::new().add_event(
-        Event::new("staked")
-            .add_attribute("token_address", token_address)
-            .add_attribute("token_id", msg.token_id)
-            .add_attribute("owner", owner)
-            .add_attribute("start_timestamp", env.block.time.to_string())
-            .add_attribute("index", (stakings_state.len() - 1).to_string()),
+        Event::new("unstake_penalty_set")
+            .add_attribute("address", address)
+            .add_attribute("curve", format!("{:?}", curve))
+            .add_attribute("charge", format!("{:?}", charge)),
     ))
 }
 
-pub fn unstake(
+pub fn set_fee_recycling(
     mut deps: DepsMut,
-    env: Env,
+    _env: Env,
     info: MessageInfo,
-    index: u64,
+    address: String,
+    share: Decimal,
 ) -> Result<Response, ContractError> {
-    let owner = info.sender.to_string();
     let store = deps.branch().storage;
-    let mut config_state = CONFIG.load(store)?;
-    let mut stakings_state = STAKINGS.may_load(store, owner.clone())?.unwrap();
-    let staking_info = stakings_state[index as usize].clone();
-    let mut staking = &mut stakings_state[index as usize];
-    let collection = COLLECTIONS.load(store, staking_info.token_address.clone())?;
-    if staking.end_timestamp != Timestamp::from_nanos(0) {
-        return Err(ContractError::AlreadyUnstaked {});
+    check_role(info.clone(), store, Role::FeeManager)?;
+    let mut collection = COLLECTIONS
+        .may_load(store, address.clone())?
+        .ok_or(ContractError::NotWhitelisted {})?;
+    if share > Decimal::one() {
+        return Err(ContractError::InvalidRecycleShare {});
     }
-    if env.block.time.seconds() - staking_info.start_timestamp.clone().seconds() < collection.cycle
-    {
-        if info.funds == vec![config_state.unstake_fee.clone()] {
-            config_state.fee_collected = config_state.fee_collected
-                + u128::from_str(&config_state.unstake_fee.clone().amount.to_string()).unwrap();
-            CONFIG.save(store, &config_state)?;
-        } else {
-            return Err(ContractError::NotEnoughUnstakeFee {});
+    collection.fee_recycle_share = share;
+    COLLECTIONS.save(store, address.clone(), &collection)?;
+    Ok(Response::new().add_event(
+        Event::new("fee_recycling_set")
+            .add_attribute("address", address)
+            .add_attribute("share", share.to_string()),
+    ))
+}
+
+const MAX_LOCK_DURATION: u64 = 63_072_000; // 2 years
+
+pub fn set_lock_tiers(
+    mut deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    address: String,
+    tiers: Vec<LockTier>,
+) -> Result<Response, ContractError> {
+    let store = deps.branch().storage;
+    let mut collection = COLLECTIONS
+        .may_load(store, address.clone())?
+        .ok_or(ContractError::NotWhitelisted {})?;
+    check_collection_admin(info.clone(), store, &collection)?;
+    for (i, tier) in tiers.iter().enumerate() {
+        if tier.duration == 0
+            || tier.duration > MAX_LOCK_DURATION
+            || tiers[..i].iter().any(|t| t.duration == tier.duration)
+        {
+            return Err(ContractError::InvalidLockTier {});
         }
     }
-    staking.end_timestamp = env.block.time;
-    let transfer_msg: CosmosMsg = CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: staking.token_address.clone(),
-        msg: to_json_binary(&Cw721ExecuteMsg::TransferNft {
-            recipient: owner.clone(),
-            token_id: staking.token_id.clone(),
-        })?,
-        funds: vec![],
-    });
-    let _ = STAKINGS.save(store, owner.clone(), &stakings_state);
-    Ok(Response::new()
-        .add_event(
-            Event::new("unstaked")
-                .add_attribute("token_address", staking_info.token_address.clone())
-                .add_attribute("token_id", staking_info.token_id.clone())
-                .add_attribute("owner", owner)
-                .add_attribute(
-                    "start_timestamp",
-                    staking_info.start_timestamp.seconds().to_string(),
-                )
-                .add_attribute("end_timestamp", env.block.time.seconds().to_string())
-                .add_attribute("index", index.to_string()),
-        )
-        .add_message(transfer_msg))
+    collection.lock_tiers = tiers.clone();
+    COLLECTIONS.save(store, address.clone(), &collection)?;
+    Ok(Response::new().add_event(
+        Event::new("lock_tiers_set")
+            .add_attribute("address", address)
+            .add_attribute(
+                "durations",
+                tiers
+                    .iter()
+                    .map(|tier| tier.duration.to_string())
+                    .collect::<Vec<String>>()
+                    .join(","),
+            ),
+    ))
 }
 
-pub fn claim(
+pub fn set_weights(
     mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    index: u64,
+    address: String,
+    token_weights: Vec<TokenWeight>,
+    trait_weights: Vec<TraitWeight>,
+    snapshot_on_stake: Option<bool>,
 ) -> Result<Response, ContractError> {
-    let owner = info.sender.to_string();
     let store = deps.branch().storage;
-    let mut stakings_state = STAKINGS.may_load(store, owner.clone())?.unwrap();
-    let staking_info = stakings_state[index as usize].clone();
     let collection = COLLECTIONS
-        .may_load(store, staking_info.token_address.clone())?
-        .unwrap();
-    let mut staking = &mut stakings_state[index as usize];
-    if staking.end_timestamp == Timestamp::from_nanos(0) {
-        return Err(ContractError::NotUnstaked {});
+        .may_load(store, address.clone())?
+        .ok_or(ContractError::NotWhitelisted {})?;
+    check_collection_admin(info.clone(), store, &collection)?;
+    // trait weights are only read from the nft metadata when snapshotting on stake
+    if !trait_weights.is_empty() && !snapshot_on_stake.unwrap_or(collection.snapshot_weights) {
+        return Err(ContractError::TraitWeightsNeedSnapshot {});
     }
-    if staking.is_paid == true {
-        return Err(ContractError::RewardAlreadyClaimed {});
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut reweighed = 0u64;
+    for token_weight in token_weights.iter() {
+        let key = (address.clone(), token_weight.token_id.clone());
+        // staked tokens without a weight snapshot move to the new weight from now on
+        let staking = stakings().may_load(store, key.clone())?.filter(|staking| {
+            staking.weight.is_none() && staking.end_timestamp == Timestamp::from_nanos(0)
+        });
+        if let Some(mut staking) = staking {
+            check_not_paused(store)?;
+            let rewards =
+                reweigh_staking(store, &mut staking, token_weight.weight, env.block.time)?;
+            stakings().save(store, key.clone(), &staking)?;
+            messages.extend(reward_messages(&staking.owner, &rewards)?);
+            reweighed += 1;
+        }
+        TOKEN_WEIGHTS.save(store, key, &token_weight.weight)?;
     }
-    staking.is_paid = true;
-    let mut reward_amount = &u128::from(collection.reward.amount)
-        * &u128::from(
-            staking_info.end_timestamp.seconds() - staking_info.start_timestamp.seconds(),
+    for trait_weight in trait_weights.iter() {
+        TRAIT_WEIGHTS.save(
+            store,
+            (
+                address.clone(),
+                trait_weight.trait_type.clone(),
+                trait_weight.value.clone(),
+            ),
+            &trait_weight.weight,
+        )?;
+    }
+    if let Some(snapshot_on_stake) = snapshot_on_stake {
+        // reweighed stakings changed the collection
+        let mut collection = COLLECTIONS.load(store, address.clone())?;
+        collection.snapshot_weights = snapshot_on_stake;
+        COLLECTIONS.save(store, address.clone(), &collection)?;
+    }
+    Ok(Response::new()
+        .add_event(
+            Event::new("weights_set")
+                .add_attribute("address", address)
+                .add_attribute("token_weights", token_weights.len().to_string())
+                .add_attribute("trait_weights", trait_weights.len().to_string())
+                .add_attribute(
+                    "snapshot_weights",
+                    snapshot_on_stake
+                        .unwrap_or(collection.snapshot_weights)
+                        .to_string(),
+                )
+                .add_attribute("reweighed", reweighed.to_string()),
         )
-        / &u128::from(collection.cycle);
-    if reward_amount > u128::from(collection.reward.amount) {
-        reward_amount = u128::from(collection.reward.amount);
-    }
-    let _ = STAKINGS.save(store, owner.clone(), &stakings_state);
-    if reward_amount > 0 {
-        if collection.pool_amount < reward_amount {
-            return Err(ContractError::NotEnoughRewardPool {});
-        }
-        let transfer_msg = BankMsg::Send {
-            to_address: owner.clone(),
-            amount: vec![coin(reward_amount, collection.clone().reward.denom)],
-        };
-        COLLECTIONS.update(
+        .add_messages(messages))
+}
+
+// pay a staking the reward accrued at its current weight and move it and its collection
+// to `weight`. in per cycle mode the new weight applies to the running cycle, so its
+// liability is moved to the new shares too.
+fn reweigh_staking(
+    store: &mut dyn Storage,
+    staking: &mut Staking,
+    weight: Decimal,
+    now: Timestamp,
+) -> Result<Vec<Asset>, ContractError> {
+    let rewards = settle_reward(store, staking, now, true)?;
+    let mut collection = COLLECTIONS.load(store, staking.token_address.clone())?;
+    let share = claim_penalty_share(&collection, staking, now);
+    let (rewards, _) = withhold_rewards(store, &mut collection, rewards, share)?;
+    let shares = weighted_shares(staking, weight)?;
+    if collection.reward_mode == RewardMode::PerCycle {
+        for i in 0..collection.rewards.len() {
+            let stream = &collection.rewards[i];
+            let running = |shares| -> Result<Decimal256, ContractError> {
+                let amount = linear_reward(&collection, stream, staking, shares, now)?
+                    .saturating_sub(accrued_reward(&collection, stream, staking, shares, now)?);
+                Ok(Decimal256::from_ratio(amount, 1u128))
+            };
+            let (before, after) = (running(staking.shares)?, running(shares)?);
+            let accrued = stream
+                .accrued_amount
+                .saturating_sub(before)
+                .checked_add(after)?;
+            collection.rewards[i].accrued_amount = accrued;
+        }
+    }
+    collection.total_shares = collection
+        .total_shares
+        .checked_sub(staking.shares)
+        .unwrap_or_default()
+        .checked_add(shares)?;
+    staking.shares = shares;
+    COLLECTIONS.save(store, staking.token_address.clone(), &collection)?;
+    Ok(rewards)
+}
+
+pub fn freeze_collection(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: String,
+    frozen: bool,
+) -> Result<Response, ContractError> {
+    let store = deps.branch().storage;
+    let mut collection = COLLECTIONS
+        .may_load(store, address.clone())?
+        .ok_or(ContractError::NotWhitelisted {})?;
+    check_collection_admin(info.clone(), store, &collection)?;
+    if frozen && !collection.is_frozen() {
+        collection.frozen_periods.push(FrozenPeriod {
+            start: env.block.time,
+            end: None,
+        });
+    } else if !frozen && collection.is_frozen() {
+        if let Some(period) = collection.frozen_periods.last_mut() {
+            period.end = Some(env.block.time);
+        }
+    }
+    COLLECTIONS.save(store, address.clone(), &collection)?;
+    Ok(Response::new().add_event(
+        Event::new("collection_frozen")
+            .add_attribute("address", address)
+            .add_attribute("frozen", frozen.to_string())
+            .add_attribute("timestamp", env.block.time.seconds().to_string()),
+    ))
+}
+
+pub fn deposit_collection_reward(
+    mut deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    let store = deps.branch().storage;
+    check_not_paused(store)?;
+    let mut collection = COLLECTIONS
+        .may_load(store, address.clone())?
+        .ok_or(ContractError::NotWhitelisted {})?;
+    if info.funds.is_empty() {
+        return Err(ContractError::NoFunds {});
+    }
+    let deposits: Vec<Asset> = info
+        .funds
+        .iter()
+        .map(|fund| Asset {
+            info: AssetInfo::Native {
+                denom: fund.denom.clone(),
+            },
+            amount: fund.amount,
+        })
+        .collect();
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for deposit in deposits.iter() {
+        credit_reward_pool(&mut collection, deposit)?;
+        messages.extend(pay_unpaid_rewards(
             store,
-            staking_info.token_address.clone(),
-            |c| -> StdResult<Collection> {
-                let col = c.clone().unwrap();
-                Ok(Collection::new(
-                    col.reward,
-                    col.cycle,
-                    col.is_whitelisted,
-                    col.spots,
-                    col.pool_amount - reward_amount,
-                ))
+            &address,
+            &mut collection,
+            &deposit.info,
+        )?);
+    }
+    COLLECTIONS.save(store, address.clone(), &collection)?;
+    Ok(Response::new()
+        .add_event(
+            Event::new("collection_reward_deposited")
+                .add_attribute("address", address)
+                .add_attribute("amount", assets_to_string(&deposits)),
+        )
+        .add_messages(messages))
+}
+
+pub fn receive_cw20(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let store = deps.branch().storage;
+    check_not_paused(store)?;
+    // the sender of the hook is the cw20 token contract
+    let deposit = Asset {
+        info: AssetInfo::Cw20 {
+            contract_addr: info.sender.to_string(),
+        },
+        amount: msg.amount,
+    };
+    match from_json(&msg.msg)? {
+        ReceiveMsg::RegisterCollection {
+            address,
+            rewards,
+            cycle,
+            spots,
+            reward_mode,
+        } => list_collection(
+            deps,
+            env,
+            msg.sender,
+            &[],
+            Some(deposit),
+            address,
+            rewards,
+            cycle,
+            spots,
+            reward_mode,
+        ),
+        ReceiveMsg::DepositCollectionReward { address } => {
+            let mut collection = COLLECTIONS
+                .may_load(store, address.clone())?
+                .ok_or(ContractError::NotWhitelisted {})?;
+            credit_reward_pool(&mut collection, &deposit)?;
+            let messages = pay_unpaid_rewards(store, &address, &mut collection, &deposit.info)?;
+            COLLECTIONS.save(store, address.clone(), &collection)?;
+            Ok(Response::new()
+                .add_event(
+                    Event::new("collection_reward_deposited")
+                        .add_attribute("address", address)
+                        .add_attribute("sender", msg.sender)
+                        .add_attribute("amount", deposit.to_string()),
+                )
+                .add_messages(messages))
+        }
+    }
+}
+
+// add a deposit to the pool of the reward stream paying the same asset.
+fn credit_reward_pool(collection: &mut Collection, deposit: &Asset) -> Result<(), ContractError> {
+    let stream = collection
+        .rewards
+        .iter_mut()
+        .find(|stream| stream.asset == deposit.info)
+        .ok_or(ContractError::UnknownRewardAsset {
+            asset: deposit.info.to_string(),
+        })?;
+    stream.pool_amount = stream.pool_amount.checked_add(deposit.amount)?;
+    Ok(())
+}
+
+const MAX_UNPAID_PAYOUTS: usize = 30;
+
+// pay rewards of an asset owed to stakers of a collection from its refilled pool. owners
+// left over are paid on a later deposit.
+fn pay_unpaid_rewards(
+    store: &mut dyn Storage,
+    address: &str,
+    collection: &mut Collection,
+    asset: &AssetInfo,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    let Some(stream) = collection
+        .rewards
+        .iter_mut()
+        .find(|stream| &stream.asset == asset)
+    else {
+        return Ok(vec![]);
+    };
+    // only owners owed the deposited asset are walked, the rest are paid by later deposits
+    let owed = UNPAID_REWARDS
+        .prefix((address.to_string(), asset.to_string()))
+        .range(store, None, None, Order::Ascending)
+        .take(MAX_UNPAID_PAYOUTS)
+        .collect::<StdResult<Vec<(String, Asset)>>>()?;
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for (owner, unpaid) in owed {
+        let amount = unpaid.amount.min(stream.pool_amount);
+        if amount.is_zero() {
+            break;
+        }
+        stream.pool_amount -= amount;
+        stream.debt_amount = stream.debt_amount.saturating_sub(amount);
+        let key = (address.to_string(), asset.to_string(), owner.clone());
+        if amount < unpaid.amount {
+            UNPAID_REWARDS.save(
+                store,
+                key,
+                &Asset {
+                    info: unpaid.info.clone(),
+                    amount: unpaid.amount - amount,
+                },
+            )?;
+        } else {
+            UNPAID_REWARDS.remove(store, key);
+        }
+        messages.extend(reward_messages(
+            &owner,
+            &[Asset {
+                info: unpaid.info,
+                amount,
+            }],
+        )?);
+    }
+    Ok(messages)
+}
+
+// owe rewards the pool could not cover to a staking owner.
+fn add_unpaid_rewards(
+    store: &mut dyn Storage,
+    address: &str,
+    owner: &str,
+    rewards: &[Asset],
+) -> Result<(), ContractError> {
+    for reward in rewards.iter() {
+        let key = (
+            address.to_string(),
+            reward.info.to_string(),
+            owner.to_string(),
+        );
+        let amount = UNPAID_REWARDS
+            .may_load(store, key.clone())?
+            .map_or(Uint128::zero(), |unpaid| unpaid.amount);
+        UNPAID_REWARDS.save(
+            store,
+            key,
+            &Asset {
+                info: reward.info.clone(),
+                amount: amount.checked_add(reward.amount)?,
             },
         )?;
-        Ok(Response::new()
-            .add_event(
-                Event::new("claimed")
-                    .add_attribute("token_address", staking_info.token_address.clone())
-                    .add_attribute("token_id", staking_info.token_id.clone())
-                    .add_attribute("owner", owner)
-                    .add_attribute(
-                        "start_timestamp",
-                        staking_info.start_timestamp.seconds().to_string(),
-                    )
-                    .add_attribute(
-                        "end_timestamp",
-                        staking_info.end_timestamp.seconds().to_string(),
-                    )
-                    .add_attribute(
-                        "reward",
-                        coin(reward_amount, collection.reward.denom).to_string(),
-                    )
-                    .add_attribute("index", index.to_string()),
+    }
+    Ok(())
+}
+
+pub fn withdraw_collection_reward(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: String,
+    asset: Asset,
+) -> Result<Response, ContractError> {
+    let store = deps.branch().storage;
+    let mut collection = COLLECTIONS
+        .may_load(store, address.clone())?
+        .ok_or(ContractError::NotWhitelisted {})?;
+    check_pool_admin(info.clone(), store, &collection)?;
+    take_unallocated_reward(&mut collection, &asset, env.block.time)?;
+    COLLECTIONS.save(store, address.clone(), &collection)?;
+    Ok(Response::new()
+        .add_event(
+            Event::new("collection_reward_withdrawn")
+                .add_attribute("address", address)
+                .add_attribute("recipient", info.sender.to_string())
+                .add_attribute("amount", asset.to_string()),
+        )
+        .add_messages(reward_messages(info.sender.as_ref(), &[asset])?))
+}
+
+pub fn reallocate_collection_reward(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    from: String,
+    to: String,
+    asset: Asset,
+) -> Result<Response, ContractError> {
+    let store = deps.branch().storage;
+    let mut from_collection = COLLECTIONS
+        .may_load(store, from.clone())?
+        .ok_or(ContractError::NotWhitelisted {})?;
+    check_pool_admin(info.clone(), store, &from_collection)?;
+    take_unallocated_reward(&mut from_collection, &asset, env.block.time)?;
+    COLLECTIONS.save(store, from.clone(), &from_collection)?;
+    let mut to_collection = COLLECTIONS
+        .may_load(store, to.clone())?
+        .ok_or(ContractError::NotWhitelisted {})?;
+    check_collection_admin(info.clone(), store, &to_collection)?;
+    credit_reward_pool(&mut to_collection, &asset)?;
+    let messages = pay_unpaid_rewards(store, &to, &mut to_collection, &asset.info)?;
+    COLLECTIONS.save(store, to.clone(), &to_collection)?;
+    Ok(Response::new()
+        .add_event(
+            Event::new("collection_reward_reallocated")
+                .add_attribute("from", from)
+                .add_attribute("to", to)
+                .add_attribute("amount", asset.to_string()),
+        )
+        .add_messages(messages))
+}
+
+pub fn set_listing_fee(
+    mut deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    fee: Option<Coin>,
+) -> Result<Response, ContractError> {
+    let store = deps.branch().storage;
+    check_role(info.clone(), store, Role::FeeManager)?;
+    let mut config_state = CONFIG.load(store)?;
+    config_state.listing_fee = fee.clone();
+    CONFIG.save(store, &config_state)?;
+    Ok(Response::new().add_event(
+        Event::new("listing_fee_set")
+            .add_attribute("fee", fee.map_or("none".to_string(), |fee| fee.to_string())),
+    ))
+}
+
+pub fn set_paused(
+    mut deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    let store = deps.branch().storage;
+    check_role(info.clone(), store, Role::Pauser)?;
+    let mut config_state = CONFIG.load(store)?;
+    config_state.paused = paused;
+    CONFIG.save(store, &config_state)?;
+    Ok(Response::new().add_event(Event::new("paused").add_attribute("paused", paused.to_string())))
+}
+
+pub fn set_emergency(
+    mut deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    emergency: bool,
+) -> Result<Response, ContractError> {
+    let store = deps.branch().storage;
+    check_role(info.clone(), store, Role::Pauser)?;
+    let mut config_state = CONFIG.load(store)?;
+    config_state.emergency = emergency;
+    CONFIG.save(store, &config_state)?;
+    Ok(Response::new()
+        .add_event(Event::new("emergency").add_attribute("emergency", emergency.to_string())))
+}
+
+pub fn withdraw_fee(
+    mut deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    fee: Coin,
+) -> Result<Response, ContractError> {
+    let store = deps.branch().storage;
+    check_role(info.clone(), store, Role::FeeManager)?;
+    // only denoms collected as fees can be withdrawn
+    let collected = FEES_COLLECTED
+        .may_load(store, fee.denom.clone())?
+        .unwrap_or_default();
+    if fee.amount > collected {
+        return Err(ContractError::NotEnoughFeeCollected {});
+    }
+    if fee.amount == collected {
+        FEES_COLLECTED.remove(store, fee.denom.clone());
+    } else {
+        FEES_COLLECTED.save(store, fee.denom.clone(), &(collected - fee.amount))?;
+    }
+
+    let transfer_msg = BankMsg::Send {
+        to_address: info.sender.to_string(),
+        amount: vec![fee.clone()],
+    };
+    Ok(Response::new()
+        .add_event(
+            Event::new("fee_withdrawn")
+                .add_attribute("address", info.sender.to_string())
+                .add_attribute("fee", fee.to_string()),
+        )
+        .add_message(transfer_msg))
+}
+
+pub fn set_fee_distribution(
+    mut deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    shares: Vec<FeeShare>,
+) -> Result<Response, ContractError> {
+    let mut validated: Vec<FeeShare> = vec![];
+    for share in shares.into_iter() {
+        let recipient = match share.recipient {
+            FeeRecipient::Address { address } => FeeRecipient::Address {
+                address: deps.api.addr_validate(&address)?.to_string(),
+            },
+            recipient => recipient,
+        };
+        validated.push(FeeShare {
+            recipient,
+            bps: share.bps,
+        });
+    }
+    let store = deps.branch().storage;
+    check_role(info.clone(), store, Role::FeeManager)?;
+    if validated.iter().any(|share| share.bps == 0)
+        || validated.iter().map(|share| share.bps).sum::<u64>() != 10_000
+    {
+        return Err(ContractError::InvalidFeeShares {});
+    }
+    for share in validated.iter() {
+        match &share.recipient {
+            FeeRecipient::CollectionCreator { collection }
+            | FeeRecipient::RewardPool { collection } => {
+                if !COLLECTIONS.has(store, collection.clone()) {
+                    return Err(ContractError::NotWhitelisted {});
+                }
+            }
+            FeeRecipient::Address { .. } => {}
+        }
+    }
+    FEE_DISTRIBUTION.save(store, &validated)?;
+    Ok(Response::new().add_event(
+        Event::new("fee_distribution_set").add_attribute(
+            "shares",
+            validated
+                .iter()
+                .map(|share| format!("{:?}:{}", share.recipient, share.bps))
+                .collect::<Vec<String>>()
+                .join(","),
+        ),
+    ))
+}
+
+// pay the collected fees of every denom to the distribution recipients. shares that
+// can not be paid, like a pool top-up in a denom the pool does not reward or a creator
+// share of a collection without admin, stay collected along with rounding dust.
+pub fn distribute_fees(
+    mut deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let store = deps.branch().storage;
+    check_not_paused(store)?;
+    let shares = FEE_DISTRIBUTION
+        .may_load(store)?
+        .filter(|shares| !shares.is_empty())
+        .ok_or(ContractError::NoFeeDistribution {})?;
+    let collected = FEES_COLLECTED
+        .range(store, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<(String, Uint128)>>>()?;
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut distributed: Vec<Coin> = vec![];
+    for (denom, amount) in collected {
+        let mut remaining = amount;
+        for share in shares.iter() {
+            let share_amount = amount.multiply_ratio(share.bps, 10_000u64);
+            if share_amount.is_zero() {
+                continue;
+            }
+            let fee = Coin {
+                denom: denom.clone(),
+                amount: share_amount,
+            };
+            let paid = match &share.recipient {
+                FeeRecipient::Address { address } => {
+                    messages.push(CosmosMsg::Bank(BankMsg::Send {
+                        to_address: address.clone(),
+                        amount: vec![fee],
+                    }));
+                    true
+                }
+                FeeRecipient::CollectionCreator { collection } => {
+                    match COLLECTIONS
+                        .may_load(store, collection.clone())?
+                        .and_then(|collection| collection.admin)
+                    {
+                        Some(admin) => {
+                            messages.push(CosmosMsg::Bank(BankMsg::Send {
+                                to_address: admin,
+                                amount: vec![fee],
+                            }));
+                            true
+                        }
+                        None => false,
+                    }
+                }
+                FeeRecipient::RewardPool {
+                    collection: address,
+                } => match COLLECTIONS.may_load(store, address.clone())? {
+                    Some(mut collection) => {
+                        let deposit = Asset {
+                            info: AssetInfo::Native {
+                                denom: denom.clone(),
+                            },
+                            amount: share_amount,
+                        };
+                        if credit_reward_pool(&mut collection, &deposit).is_ok() {
+                            messages.extend(pay_unpaid_rewards(
+                                store,
+                                address,
+                                &mut collection,
+                                &deposit.info,
+                            )?);
+                            COLLECTIONS.save(store, address.clone(), &collection)?;
+                            true
+                        } else {
+                            false
+                        }
+                    }
+                    None => false,
+                },
+            };
+            if paid {
+                remaining -= share_amount;
+            }
+        }
+        if remaining == amount {
+            continue;
+        }
+        if remaining.is_zero() {
+            FEES_COLLECTED.remove(store, denom.clone());
+        } else {
+            FEES_COLLECTED.save(store, denom.clone(), &remaining)?;
+        }
+        distributed.push(Coin {
+            denom,
+            amount: amount - remaining,
+        });
+    }
+    Ok(Response::new()
+        .add_event(
+            Event::new("fees_distributed").add_attribute("amount", coins_to_string(&distributed)),
+        )
+        .add_messages(messages))
+}
+
+// charge one of the accepted fees from the funds sent. returns the charged fee and the
+// funds to refund.
+fn charge_fee(fees: &[Coin], funds: &[Coin]) -> Result<(Option<Coin>, Vec<Coin>), ContractError> {
+    if fees.is_empty() {
+        return Ok((None, funds.to_vec()));
+    }
+    let fee = fees
+        .iter()
+        .find(|fee| {
+            funds
+                .iter()
+                .any(|fund| fund.denom == fee.denom && fund.amount >= fee.amount)
+        })
+        .ok_or(ContractError::NotEnoughUnstakeFee {})?;
+    let refund = funds
+        .iter()
+        .map(|fund| Coin {
+            denom: fund.denom.clone(),
+            amount: if fund.denom == fee.denom {
+                fund.amount - fee.amount
+            } else {
+                fund.amount
+            },
+        })
+        .filter(|fund| !fund.amount.is_zero())
+        .collect();
+    Ok((Some(fee.clone()), refund))
+}
+
+fn add_collected_fee(store: &mut dyn Storage, fee: &Coin) -> Result<(), ContractError> {
+    let collected = FEES_COLLECTED
+        .may_load(store, fee.denom.clone())?
+        .unwrap_or_default();
+    FEES_COLLECTED.save(
+        store,
+        fee.denom.clone(),
+        &collected.checked_add(fee.amount)?,
+    )?;
+    Ok(())
+}
+
+pub fn coins_to_string(coins: &[Coin]) -> String {
+    if coins.is_empty() {
+        return "none".to_string();
+    }
+    coins
+        .iter()
+        .map(|coin| coin.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+pub fn stake(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: UniversalNftReceiveMsg,
+) -> Result<Response, ContractError> {
+    let token_address = info.sender.to_string();
+    let owner = msg.clone().sender;
+    let store = deps.branch().storage;
+    check_not_paused(store)?;
+    if legacy_stakings_pending(store) {
+        return Err(ContractError::MigrationPending {});
+    }
+    let collection = COLLECTIONS.may_load(store, token_address.clone())?;
+    if collection.is_none() {
+        return Err(ContractError::NotWhitelisted {});
+    }
+    let mut collection = collection.unwrap();
+    if !collection.is_whitelisted {
+        return Err(ContractError::NotWhitelisted {});
+    }
+    if collection.is_frozen() {
+        return Err(ContractError::CollectionFrozen {});
+    }
+    if collection.available_spots() == 0 {
+        return Err(ContractError::NoSpotsAvailable {});
+    }
+    let key = (token_address.clone(), msg.token_id.clone());
+    if stakings().has(store, key.clone()) {
+        return Err(ContractError::AlreadyStaked {});
+    }
+    let stake_msg: StakeMsg = if msg.msg.is_empty() {
+        StakeMsg::default()
+    } else {
+        from_json(&msg.msg)?
+    };
+    let lock_tier = match stake_msg.lock_duration {
+        Some(duration) => Some(
+            collection
+                .lock_tiers
+                .iter()
+                .find(|tier| tier.duration == duration)
+                .cloned()
+                .ok_or(ContractError::InvalidLockTier {})?,
+        ),
+        None => None,
+    };
+    let weight = if collection.snapshot_weights {
+        Some(snapshot_weight(
+            deps.as_ref(),
+            token_address.clone(),
+            msg.token_id.clone(),
+        )?)
+    } else {
+        None
+    };
+    let store = deps.branch().storage;
+    let mut staking = Staking::new(
+        owner.clone(),
+        token_address.clone(),
+        msg.clone().token_id,
+        env.block.time,
+        false,
+    );
+    staking.lock_tier = lock_tier;
+    staking.weight = weight;
+    staking.unbonding_duration = collection.unbonding_duration;
+    staking.shares = staking_shares(store, &staking)?;
+    update_reward_per_share(&mut collection, env.block.time)?;
+    if collection.reward_mode == RewardMode::Shared {
+        staking.reward_debts = reward_debts(&collection);
+    }
+    collection.staked += 1;
+    collection.total_shares = collection.total_shares.checked_add(staking.shares)?;
+    COLLECTIONS.save(store, token_address.clone(), &collection)?;
+    stakings().save(store, key, &staking)?;
+    Ok(Response::new().add_event(
+        Event::new("staked")
+            .add_attribute("token_address", token_address)
+            .add_attribute("token_id", msg.token_id)
+            .add_attribute("owner", owner)
+            .add_attribute("start_timestamp", env.block.time.to_string())
+            .add_attribute(
+                "lock_duration",
+                stake_msg.lock_duration.unwrap_or_default().to_string(),
             )
-            .add_message(transfer_msg))
+            .add_attribute("available_spots", collection.available_spots().to_string()),
+    ))
+}
+
+pub fn unstake(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_address: String,
+    token_id: String,
+    recipient: Option<String>,
+) -> Result<Response, ContractError> {
+    let recipient = match recipient {
+        Some(recipient) => Some(deps.api.addr_validate(&recipient)?.to_string()),
+        None => None,
+    };
+    let store = deps.branch().storage;
+    // rewards are not settled while paused, emergency unstake stays available
+    check_not_paused(store)?;
+    let config_state = CONFIG.load(store)?;
+    let key = (token_address, token_id);
+    let mut staking = stakings()
+        .may_load(store, key.clone())?
+        .ok_or(ContractError::NotStaked {})?;
+    let recipient = check_operator(
+        store,
+        &env,
+        &info,
+        &staking,
+        |approval| approval.unstake,
+        recipient,
+    )?;
+    let owner = staking.owner.clone();
+    let staking_info = staking.clone();
+    let collection = COLLECTIONS.load(store, staking_info.token_address.clone())?;
+    if staking.end_timestamp != Timestamp::from_nanos(0) {
+        return Err(ContractError::AlreadyUnstaked {});
+    }
+    let (due_fees, reward_share) =
+        unstake_fee(&config_state, &collection, &staking_info, env.block.time)?;
+    let (fee, refund) = charge_fee(&due_fees, &info.funds)?;
+    staking.end_timestamp = env.block.time;
+    // settle the reward accrued since the last claim, the nft is released even when the
+    // pool can not cover it
+    let rewards = settle_reward(store, &mut staking, env.block.time, true)?;
+    // release the spot and shares taken by this staking
+    let mut collection = COLLECTIONS.load(store, staking_info.token_address.clone())?;
+    let (rewards, withheld) = withhold_rewards(store, &mut collection, rewards, reward_share)?;
+    let recycled = match &fee {
+        Some(fee) => collect_unstake_fee(store, &mut collection, fee)?,
+        None => Uint128::zero(),
+    };
+    collection.staked = collection.staked.saturating_sub(1);
+    collection.total_shares = collection
+        .total_shares
+        .checked_sub(staking.shares)
+        .unwrap_or_default();
+    COLLECTIONS.save(store, staking_info.token_address.clone(), &collection)?;
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if !refund.is_empty() {
+        // overpaid funds go back to the sender, who may be an operator
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: refund,
+        }));
+    }
+    if staking.unbonding_duration > 0 {
+        // the nft is kept until the unbonding period set at stake time is over
+        staking.release_timestamp = Some(checked_plus_seconds(
+            env.block.time,
+            staking.unbonding_duration,
+        )?);
+        staking.recipient = Some(recipient.clone());
+        stakings().save(store, key, &staking)?;
     } else {
-        Ok(Response::new().add_event(
+        stakings().remove(store, key)?;
+        messages.push(nft_transfer_message(&staking, &recipient)?);
+    }
+    messages.extend(reward_messages(&recipient, &rewards)?);
+    Ok(Response::new()
+        .add_event(
             Event::new("unstaked")
                 .add_attribute("token_address", staking_info.token_address.clone())
                 .add_attribute("token_id", staking_info.token_id.clone())
-                .add_attribute("owner", owner)
+                .add_attribute("owner", owner.clone())
+                .add_attribute("recipient", recipient.clone())
                 .add_attribute(
                     "start_timestamp",
                     staking_info.start_timestamp.seconds().to_string(),
                 )
+                .add_attribute("end_timestamp", env.block.time.seconds().to_string())
                 .add_attribute(
-                    "end_timestamp",
-                    staking_info.end_timestamp.seconds().to_string(),
+                    "release_timestamp",
+                    staking
+                        .release_timestamp
+                        .unwrap_or(env.block.time)
+                        .seconds()
+                        .to_string(),
                 )
+                .add_attribute("fee", fee.map_or("none".to_string(), |fee| fee.to_string()))
+                .add_attribute("recycled_fee", recycled.to_string())
+                .add_attribute("withheld_reward", assets_to_string(&withheld))
+                .add_attribute("reward", assets_to_string(&rewards)),
+        )
+        .add_messages(messages))
+}
+
+// early unstake fee of a staking at `now`: the accepted fee coins and the share of the
+// reward withheld. locked stakings pay the tier penalty until they unlock, others pay
+// the collection penalty during their first cycle.
+pub fn unstake_fee(
+    config: &Config,
+    collection: &Collection,
+    staking: &Staking,
+    now: Timestamp,
+) -> Result<(Vec<Coin>, Decimal), ContractError> {
+    if let (Some(tier), Some(unlock_timestamp)) =
+        (staking.lock_tier.clone(), staking.unlock_timestamp()?)
+    {
+        if now < unlock_timestamp {
+            let penalty = tier
+                .early_unstake_penalty
+                .ok_or(ContractError::LockedUp { unlock_timestamp })?;
+            return Ok((vec![penalty], Decimal::zero()));
+        }
+        return Ok((vec![], Decimal::zero()));
+    }
+    let Some(factor) = penalty_factor(collection, staking, now) else {
+        return Ok((vec![], Decimal::zero()));
+    };
+    match &collection.penalty_charge {
+        PenaltyCharge::Coin => {
+            let fees = collection
+                .unstake_fees
+                .clone()
+                .unwrap_or(config.unstake_fees.clone());
+            let mut scaled: Vec<Coin> = vec![];
+            for fee in fees.into_iter() {
+                let amount = fee
+                    .amount
+                    .checked_multiply_ratio(factor.atomics(), Decimal::one().atomics())?;
+                // a fee decayed to zero waives the unstake fee
+                if amount.is_zero() {
+                    return Ok((vec![], Decimal::zero()));
+                }
+                scaled.push(Coin {
+                    denom: fee.denom,
+                    amount,
+                });
+            }
+            Ok((scaled, Decimal::zero()))
+        }
+        PenaltyCharge::RewardShare { percent } => Ok((vec![], *percent * factor)),
+    }
+}
+
+// decay of the collection penalty for a staking at `now`, none after its first cycle.
+fn penalty_factor(collection: &Collection, staking: &Staking, now: Timestamp) -> Option<Decimal> {
+    let elapsed = now
+        .seconds()
+        .saturating_sub(staking.start_timestamp.seconds());
+    if elapsed >= collection.cycle {
+        return None;
+    }
+    Some(match &collection.penalty_curve {
+        PenaltyCurve::Flat => Decimal::one(),
+        PenaltyCurve::Linear => Decimal::from_ratio(collection.cycle - elapsed, collection.cycle),
+        PenaltyCurve::Stepwise { steps } => steps
+            .iter()
+            .rev()
+            .find(|step| step.elapsed <= elapsed)
+            .map_or(Decimal::one(), |step| step.percent),
+    })
+}
+
+// share of the reward withheld from a claim. stakings without a lock tier pay the reward
+// share penalty on claims too, claiming before unstaking would avoid it otherwise.
+fn claim_penalty_share(collection: &Collection, staking: &Staking, now: Timestamp) -> Decimal {
+    if staking.lock_tier.is_some() || staking.end_timestamp != Timestamp::from_nanos(0) {
+        return Decimal::zero();
+    }
+    match &collection.penalty_charge {
+        PenaltyCharge::RewardShare { percent } => penalty_factor(collection, staking, now)
+            .map_or(Decimal::zero(), |factor| *percent * factor),
+        PenaltyCharge::Coin => Decimal::zero(),
+    }
+}
+
+// book an unstake fee. the recycle share of the collection is paid into its reward pool
+// when the pool rewards the fee denom, owed rewards are paid from it on the next deposit.
+fn collect_unstake_fee(
+    store: &mut dyn Storage,
+    collection: &mut Collection,
+    fee: &Coin,
+) -> Result<Uint128, ContractError> {
+    let recycle = Asset {
+        info: AssetInfo::Native {
+            denom: fee.denom.clone(),
+        },
+        amount: fee.amount.checked_multiply_ratio(
+            collection.fee_recycle_share.atomics(),
+            Decimal::one().atomics(),
+        )?,
+    };
+    let recycled = if !recycle.amount.is_zero()
+        && collection
+            .rewards
+            .iter()
+            .any(|stream| stream.asset == recycle.info)
+    {
+        credit_reward_pool(collection, &recycle)?;
+        recycle.amount
+    } else {
+        Uint128::zero()
+    };
+    if fee.amount > recycled {
+        add_collected_fee(
+            store,
+            &Coin {
+                denom: fee.denom.clone(),
+                amount: fee.amount - recycled,
+            },
+        )?;
+    }
+    Ok(recycled)
+}
+
+// withhold a share of the rewards paid on unstake. native rewards are booked as
+// collected fees, cw20 rewards stay in the collection reward pool.
+fn withhold_rewards(
+    store: &mut dyn Storage,
+    collection: &mut Collection,
+    rewards: Vec<Asset>,
+    share: Decimal,
+) -> Result<(Vec<Asset>, Vec<Asset>), ContractError> {
+    if share.is_zero() {
+        return Ok((rewards, vec![]));
+    }
+    let mut paid: Vec<Asset> = vec![];
+    let mut withheld: Vec<Asset> = vec![];
+    for reward in rewards.into_iter() {
+        let amount = reward
+            .amount
+            .checked_multiply_ratio(share.atomics(), Decimal::one().atomics())?;
+        if !amount.is_zero() {
+            match &reward.info {
+                AssetInfo::Native { denom } => add_collected_fee(
+                    store,
+                    &Coin {
+                        denom: denom.clone(),
+                        amount,
+                    },
+                )?,
+                AssetInfo::Cw20 { .. } => credit_reward_pool(
+                    collection,
+                    &Asset {
+                        info: reward.info.clone(),
+                        amount,
+                    },
+                )?,
+            }
+            withheld.push(Asset {
+                info: reward.info.clone(),
+                amount,
+            });
+        }
+        if reward.amount > amount {
+            paid.push(Asset {
+                info: reward.info,
+                amount: reward.amount - amount,
+            });
+        }
+    }
+    Ok((paid, withheld))
+}
+
+const DEFAULT_UNBONDED_LIMIT: u32 = 30;
+const MAX_UNBONDED_LIMIT: u32 = 100;
+
+// release the unbonded nfts of an owner to the recipients set on unstake. operators
+// approved for unstaking may withdraw on behalf of the owner.
+pub fn withdraw_unbonded(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: Option<String>,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let owner = match owner {
+        Some(owner) => deps.api.addr_validate(&owner)?.to_string(),
+        None => info.sender.to_string(),
+    };
+    let limit = limit
+        .unwrap_or(DEFAULT_UNBONDED_LIMIT)
+        .min(MAX_UNBONDED_LIMIT) as usize;
+    let store = deps.branch().storage;
+    if owner != info.sender {
+        load_approval(store, &env, &owner, &info, |approval| approval.unstake)?;
+    }
+    // only the stakings released by now are read, earliest first
+    let until = Bound::exclusive((
+        env.block.time.nanos().saturating_add(1),
+        (String::new(), String::new()),
+    ));
+    let released = stakings()
+        .idx
+        .release
+        .sub_prefix(owner.clone())
+        .range(store, None, Some(until), Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<((String, String), Staking)>>>()?;
+    if released.is_empty() {
+        return Err(ContractError::NothingToWithdraw {});
+    }
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for (key, staking) in released.iter() {
+        stakings().remove(store, key.clone())?;
+        let recipient = staking.recipient.clone().unwrap_or(owner.clone());
+        messages.push(nft_transfer_message(staking, &recipient)?);
+    }
+    Ok(Response::new()
+        .add_event(
+            Event::new("unbonded_withdrawn")
+                .add_attribute("owner", owner)
                 .add_attribute(
-                    "reward",
-                    coin(reward_amount, collection.reward.denom).to_string(),
+                    "tokens",
+                    released
+                        .iter()
+                        .map(|(_, staking)| {
+                            format!("{}:{}", staking.token_address, staking.token_id)
+                        })
+                        .collect::<Vec<String>>()
+                        .join(","),
+                ),
+        )
+        .add_messages(messages))
+}
+
+fn nft_transfer_message(staking: &Staking, recipient: &str) -> StdResult<CosmosMsg> {
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: staking.token_address.clone(),
+        msg: to_json_binary(&Cw721ExecuteMsg::TransferNft {
+            recipient: recipient.to_string(),
+            token_id: staking.token_id.clone(),
+        })?,
+        funds: vec![],
+    }))
+}
+
+// return a staked nft without fees or rewards, only while the emergency mode is on.
+pub fn emergency_unstake(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_address: String,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let owner = info.sender.to_string();
+    let store = deps.branch().storage;
+    if !CONFIG.load(store)?.emergency {
+        return Err(ContractError::NotEmergency {});
+    }
+    let key = (token_address.clone(), token_id.clone());
+    let staking = load_owned_staking(store, key.clone(), &owner)?;
+    // unbonding stakings already released their spot and reward
+    if staking.end_timestamp == Timestamp::from_nanos(0) {
+        let mut collection = COLLECTIONS.load(store, token_address.clone())?;
+        // the forfeited reward stops reserving pool funds. a faulty reward state must not
+        // block the withdrawal, the accumulators are left alone when it fails.
+        let mut released = collection.clone();
+        if release_accrued_reward(&mut released, &staking, env.block.time).is_ok() {
+            collection = released;
+        }
+        collection.staked = collection.staked.saturating_sub(1);
+        collection.total_shares = collection
+            .total_shares
+            .checked_sub(staking.shares)
+            .unwrap_or_default();
+        COLLECTIONS.save(store, token_address.clone(), &collection)?;
+    }
+    stakings().remove(store, key)?;
+    let recipient = staking.recipient.clone().unwrap_or(owner.clone());
+    let transfer_msg = nft_transfer_message(&staking, &recipient)?;
+    Ok(Response::new()
+        .add_event(
+            Event::new("emergency_unstaked")
+                .add_attribute("token_address", token_address)
+                .add_attribute("token_id", token_id)
+                .add_attribute("owner", owner)
+                .add_attribute("recipient", recipient)
+                .add_attribute("end_timestamp", env.block.time.seconds().to_string()),
+        )
+        .add_message(transfer_msg))
+}
+
+pub fn claim(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_address: String,
+    token_id: String,
+    recipient: Option<String>,
+) -> Result<Response, ContractError> {
+    let recipient = match recipient {
+        Some(recipient) => Some(deps.api.addr_validate(&recipient)?.to_string()),
+        None => None,
+    };
+    let store = deps.branch().storage;
+    check_not_paused(store)?;
+    let key = (token_address, token_id);
+    let mut staking = stakings()
+        .may_load(store, key.clone())?
+        .ok_or(ContractError::NotStaked {})?;
+    let recipient = check_operator(
+        store,
+        &env,
+        &info,
+        &staking,
+        |approval| approval.claim,
+        recipient,
+    )?;
+    let owner = staking.owner.clone();
+    let staking_info = staking.clone();
+    if staking.is_paid {
+        return Err(ContractError::RewardAlreadyClaimed {});
+    }
+    // still staked positions accrue until now, unstaked ones until their end
+    let claim_timestamp = if staking.end_timestamp == Timestamp::from_nanos(0) {
+        env.block.time
+    } else {
+        staking.end_timestamp
+    };
+    let rewards = settle_reward(store, &mut staking, claim_timestamp, false)?;
+    let mut collection = COLLECTIONS.load(store, staking.token_address.clone())?;
+    let share = claim_penalty_share(&collection, &staking, env.block.time);
+    let (rewards, withheld) = withhold_rewards(store, &mut collection, rewards, share)?;
+    if !withheld.is_empty() {
+        COLLECTIONS.save(store, staking.token_address.clone(), &collection)?;
+    }
+    stakings().save(store, key, &staking)?;
+    let event = Event::new("claimed")
+        .add_attribute("token_address", staking_info.token_address.clone())
+        .add_attribute("token_id", staking_info.token_id.clone())
+        .add_attribute("owner", owner.clone())
+        .add_attribute("recipient", recipient.clone())
+        .add_attribute(
+            "start_timestamp",
+            staking_info.start_timestamp.seconds().to_string(),
+        )
+        .add_attribute(
+            "end_timestamp",
+            staking_info.end_timestamp.seconds().to_string(),
+        )
+        .add_attribute(
+            "last_claimed_timestamp",
+            staking_info.last_claimed_timestamp.seconds().to_string(),
+        )
+        .add_attribute("claimed_timestamp", claim_timestamp.seconds().to_string())
+        .add_attribute("withheld_reward", assets_to_string(&withheld))
+        .add_attribute("reward", assets_to_string(&rewards));
+    Ok(Response::new()
+        .add_event(event)
+        .add_messages(reward_messages(&recipient, &rewards)?))
+}
+
+// check the sender may act on a staking, as its owner or as an operator approved for
+// the action. returns the address rewards and the nft are sent to, only the owner and
+// operators allowed to set a recipient may send them elsewhere.
+fn check_operator(
+    store: &dyn Storage,
+    env: &Env,
+    info: &MessageInfo,
+    staking: &Staking,
+    allowed: fn(&OperatorApproval) -> bool,
+    recipient: Option<String>,
+) -> Result<String, ContractError> {
+    let sender = info.sender.to_string();
+    if staking.owner == sender {
+        return Ok(recipient.unwrap_or(sender));
+    }
+    let approval = load_approval(store, env, &staking.owner, info, allowed)?;
+    match recipient {
+        Some(recipient) if recipient != staking.owner => {
+            if !approval.set_recipient {
+                return Err(ContractError::Unauthorized {});
+            }
+            Ok(recipient)
+        }
+        _ => Ok(staking.owner.clone()),
+    }
+}
+
+// load the unexpired approval of the sender as operator of `owner`, allowing the action.
+fn load_approval(
+    store: &dyn Storage,
+    env: &Env,
+    owner: &str,
+    info: &MessageInfo,
+    allowed: fn(&OperatorApproval) -> bool,
+) -> Result<OperatorApproval, ContractError> {
+    let approval = OPERATORS
+        .may_load(store, (owner.to_string(), info.sender.to_string()))?
+        .ok_or(ContractError::Unauthorized {})?;
+    if !allowed(&approval)
+        || approval
+            .expiry
+            .is_some_and(|expiry| expiry.is_expired(&env.block))
+    {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(approval)
+}
+
+// load a staking position and check it belongs to the given owner.
+fn load_owned_staking(
+    store: &dyn Storage,
+    key: (String, String),
+    owner: &String,
+) -> Result<Staking, ContractError> {
+    let staking = stakings()
+        .may_load(store, key)?
+        .ok_or(ContractError::NotStaked {})?;
+    if &staking.owner != owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(staking)
+}
+
+// token weight from the weight table, or the product of its on-chain trait weights.
+fn snapshot_weight(
+    deps: Deps,
+    token_address: String,
+    token_id: String,
+) -> Result<Decimal, ContractError> {
+    let token_weight =
+        TOKEN_WEIGHTS.may_load(deps.storage, (token_address.clone(), token_id.clone()))?;
+    if let Some(weight) = token_weight {
+        return Ok(weight);
+    }
+    let nft_info: TraitNftInfoResponse = deps
+        .querier
+        .query_wasm_smart(token_address.clone(), &Cw721QueryMsg::NftInfo { token_id })?;
+    let mut weight = Decimal::one();
+    let attributes = nft_info
+        .extension
+        .and_then(|extension| extension.attributes)
+        .unwrap_or_default();
+    for attribute in attributes {
+        let trait_weight = TRAIT_WEIGHTS.may_load(
+            deps.storage,
+            (token_address.clone(), attribute.trait_type, attribute.value),
+        )?;
+        if let Some(trait_weight) = trait_weight {
+            weight = weight.checked_mul(trait_weight)?;
+        }
+    }
+    Ok(weight)
+}
+
+// reward weight of a staking, its snapshot or the current token weight.
+fn staking_weight(store: &dyn Storage, staking: &Staking) -> StdResult<Decimal> {
+    if let Some(weight) = staking.weight {
+        return Ok(weight);
+    }
+    Ok(TOKEN_WEIGHTS
+        .may_load(
+            store,
+            (staking.token_address.clone(), staking.token_id.clone()),
+        )?
+        .unwrap_or(Decimal::one()))
+}
+
+// reward shares of a staking, its weight times the lock-up tier multiplier.
+fn staking_shares(store: &dyn Storage, staking: &Staking) -> Result<Decimal, ContractError> {
+    weighted_shares(staking, staking_weight(store, staking)?)
+}
+
+// reward shares of a staking at `weight`.
+fn weighted_shares(staking: &Staking, weight: Decimal) -> Result<Decimal, ContractError> {
+    let mut shares = weight;
+    if let Some(tier) = &staking.lock_tier {
+        shares = shares.checked_mul(tier.multiplier)?;
+    }
+    Ok(shares)
+}
+
+// reward of a stream accrued by a staking from its start until the given time,
+// integrated over the schedules overlapping the staking.
+pub fn accrued_reward(
+    collection: &Collection,
+    stream: &RewardStream,
+    staking: &Staking,
+    shares: Decimal,
+    until: Timestamp,
+) -> Result<Uint128, ContractError> {
+    // per cycle mode only counts the staking cycles completed so far
+    let until = match collection.reward_mode {
+        RewardMode::PerCycle => {
+            let elapsed = until
+                .seconds()
+                .saturating_sub(staking.start_timestamp.seconds());
+            staking
+                .start_timestamp
+                .plus_seconds(elapsed / collection.cycle * collection.cycle)
+        }
+        _ => until,
+    };
+    linear_reward(collection, stream, staking, shares, until)
+}
+
+// reward of a stream accrued by a staking from its start until the given time, counting
+// partial cycles.
+fn linear_reward(
+    collection: &Collection,
+    stream: &RewardStream,
+    staking: &Staking,
+    shares: Decimal,
+    until: Timestamp,
+) -> Result<Uint128, ContractError> {
+    let mut reward_amount = Uint128::zero();
+    for schedule in stream.schedules.iter() {
+        let from = schedule.start.max(staking.start_timestamp);
+        let to = schedule.end.map_or(until, |end| end.min(until));
+        // frozen periods do not accrue rewards
+        let elapsed = collection.active_seconds(from, to);
+        reward_amount = reward_amount.checked_add(
+            schedule
+                .amount
+                .checked_mul(Uint128::from(elapsed))?
+                .checked_div(Uint128::from(schedule.cycle))?,
+        )?;
+    }
+    // token weight and lock-up tier scale the reward
+    Ok(reward_amount.checked_multiply_ratio(shares.atomics(), Decimal::one().atomics())?)
+}
+
+// advance the accumulators to `until`, splitting what every stream emitted since the last
+// update over the shares staked meanwhile in shared mode, and adding what the stakers
+// accrued meanwhile to the stream liabilities in every mode.
+pub fn update_reward_per_share(
+    collection: &mut Collection,
+    until: Timestamp,
+) -> Result<(), ContractError> {
+    if until <= collection.reward_updated {
+        return Ok(());
+    }
+    if collection.total_shares.is_zero() {
+        // nothing is accrued without stakers, what is left is rounding dust
+        for stream in collection.rewards.iter_mut() {
+            stream.accrued_amount = Decimal256::zero();
+        }
+    } else {
+        let total_shares = Decimal256::from(collection.total_shares);
+        for i in 0..collection.rewards.len() {
+            let mut emitted = Decimal256::zero();
+            for schedule in collection.rewards[i].schedules.iter() {
+                let from = schedule.start.max(collection.reward_updated);
+                let to = schedule.end.map_or(until, |end| end.min(until));
+                let elapsed = collection.active_seconds(from, to);
+                emitted = emitted.checked_add(Decimal256::checked_from_ratio(
+                    schedule.amount.full_mul(elapsed),
+                    schedule.cycle,
+                )?)?;
+            }
+            let shared = collection.reward_mode == RewardMode::Shared;
+            let stream = &mut collection.rewards[i];
+            if shared {
+                stream.reward_per_share = stream
+                    .reward_per_share
+                    .checked_add(emitted.checked_div(total_shares)?)?;
+            } else {
+                // every share accrues the full emission in linear and per cycle modes
+                emitted = emitted.checked_mul(total_shares)?;
+            }
+            stream.accrued_amount = stream.accrued_amount.checked_add(emitted)?;
+        }
+    }
+    collection.reward_updated = until;
+    Ok(())
+}
+
+// current accumulator of every stream, the checkpoint of a settled staking.
+fn reward_debts(collection: &Collection) -> Vec<RewardDebt> {
+    collection
+        .rewards
+        .iter()
+        .map(|stream| RewardDebt {
+            asset: stream.asset.clone(),
+            reward_per_share: stream.reward_per_share,
+        })
+        .collect()
+}
+
+// shared mode reward of a stream accumulated since the staking checkpoint.
+fn pending_shared_reward(
+    stream: &RewardStream,
+    staking: &Staking,
+) -> Result<Uint128, ContractError> {
+    let paid = staking
+        .reward_debts
+        .iter()
+        .find(|debt| debt.asset == stream.asset)
+        .map_or(Decimal256::zero(), |debt| debt.reward_per_share);
+    let reward_amount = stream
+        .reward_per_share
+        .checked_sub(paid)?
+        .checked_mul(Decimal256::from(staking.shares))?
+        .to_uint_floor();
+    Ok(Uint128::try_from(reward_amount)?)
+}
+
+// reward of every stream accrued by a staking since its checkpoint, in stream order.
+// shared mode accumulators must be up to date.
+pub fn pending_rewards(
+    collection: &Collection,
+    staking: &Staking,
+    until: Timestamp,
+) -> Result<Vec<Uint128>, ContractError> {
+    // weight changes settle the staking before its shares change, so they are not retroactive
+    let shares = staking.shares;
+    collection
+        .rewards
+        .iter()
+        .map(|stream| {
+            if collection.reward_mode == RewardMode::Shared {
+                pending_shared_reward(stream, staking)
+            } else {
+                Ok(
+                    accrued_reward(collection, stream, staking, shares, until)?.checked_sub(
+                        accrued_reward(
+                            collection,
+                            stream,
+                            staking,
+                            shares,
+                            staking.last_claimed_timestamp,
+                        )?,
+                    )?,
                 )
-                .add_attribute("index", index.to_string()),
-        ))
+            }
+        })
+        .collect()
+}
+
+// rewards of every stream a collection owes its stakers: accrued but unclaimed rewards
+// and unpaid remainders.
+fn owed_rewards(collection: &Collection, now: Timestamp) -> Result<Vec<Uint128>, ContractError> {
+    let mut collection = collection.clone();
+    update_reward_per_share(&mut collection, now)?;
+    collection
+        .rewards
+        .iter()
+        .map(|stream| {
+            let accrued = Uint128::try_from(stream.accrued_amount.to_uint_ceil())?;
+            Ok(stream.debt_amount.checked_add(accrued)?)
+        })
+        .collect()
+}
+
+// take an asset out of a collection reward pool, leaving what is owed to stakers.
+fn take_unallocated_reward(
+    collection: &mut Collection,
+    asset: &Asset,
+    now: Timestamp,
+) -> Result<(), ContractError> {
+    let i = collection
+        .rewards
+        .iter()
+        .position(|stream| stream.asset == asset.info)
+        .ok_or(ContractError::UnknownRewardAsset {
+            asset: asset.info.to_string(),
+        })?;
+    let owed = owed_rewards(collection, now)?;
+    let available = collection.rewards[i].pool_amount.saturating_sub(owed[i]);
+    if asset.amount > available {
+        return Err(ContractError::PoolFundsAllocated { available });
+    }
+    collection.rewards[i].pool_amount -= asset.amount;
+    Ok(())
+}
+
+// drop what an active staking accrued since its checkpoint from the stream liabilities,
+// including the running cycle in per cycle mode.
+fn release_accrued_reward(
+    collection: &mut Collection,
+    staking: &Staking,
+    now: Timestamp,
+) -> Result<(), ContractError> {
+    update_reward_per_share(collection, now)?;
+    for i in 0..collection.rewards.len() {
+        let stream = &collection.rewards[i];
+        let accrued = match collection.reward_mode {
+            RewardMode::Shared => pending_shared_reward(stream, staking)?,
+            _ => linear_reward(collection, stream, staking, staking.shares, now)?.saturating_sub(
+                accrued_reward(
+                    collection,
+                    stream,
+                    staking,
+                    staking.shares,
+                    staking.last_claimed_timestamp,
+                )?,
+            ),
+        };
+        collection.rewards[i].accrued_amount = stream
+            .accrued_amount
+            .saturating_sub(Decimal256::from_ratio(accrued, 1u128));
+    }
+    Ok(())
+}
+
+// pay the reward a staking accrued until `until` from the pool. what the pool can not
+// cover is owed under the pay available policy, or whenever `allow_debt` is set.
+fn settle_reward(
+    store: &mut dyn Storage,
+    staking: &mut Staking,
+    until: Timestamp,
+    allow_debt: bool,
+) -> Result<Vec<Asset>, ContractError> {
+    let mut collection = COLLECTIONS.load(store, staking.token_address.clone())?;
+    let shared = collection.reward_mode == RewardMode::Shared;
+    update_reward_per_share(&mut collection, until)?;
+    let pending = pending_rewards(&collection, staking, until)?;
+    let ended = staking.end_timestamp != Timestamp::from_nanos(0);
+    for (i, reward_amount) in pending.iter().enumerate() {
+        let stream = &collection.rewards[i];
+        let mut settled = *reward_amount;
+        if ended && collection.reward_mode == RewardMode::PerCycle {
+            // the partial cycle is forfeited on unstake
+            settled = settled.checked_add(
+                linear_reward(&collection, stream, staking, staking.shares, until)?.saturating_sub(
+                    accrued_reward(&collection, stream, staking, staking.shares, until)?,
+                ),
+            )?;
+        }
+        collection.rewards[i].accrued_amount = stream
+            .accrued_amount
+            .saturating_sub(Decimal256::from_ratio(settled, 1u128));
+    }
+    let mut rewards: Vec<Asset> = vec![];
+    let mut unpaid: Vec<Asset> = vec![];
+    for (i, reward_amount) in pending.into_iter().enumerate() {
+        let stream = &collection.rewards[i];
+        if reward_amount.is_zero() {
+            continue;
+        }
+        let mut paid_amount = reward_amount;
+        if stream.pool_amount < reward_amount {
+            if collection.underfunded_policy == UnderfundedPolicy::Reject && !allow_debt {
+                return Err(ContractError::NotEnoughRewardPool {});
+            }
+            // pay out the pool and owe the remainder
+            paid_amount = stream.pool_amount;
+            unpaid.push(Asset {
+                info: stream.asset.clone(),
+                amount: reward_amount - paid_amount,
+            });
+            collection.rewards[i].debt_amount = collection.rewards[i]
+                .debt_amount
+                .checked_add(reward_amount - paid_amount)?;
+        }
+        if !paid_amount.is_zero() {
+            rewards.push(Asset {
+                info: collection.rewards[i].asset.clone(),
+                amount: paid_amount,
+            });
+            collection.rewards[i].pool_amount -= paid_amount;
+        }
+    }
+    if !unpaid.is_empty() {
+        add_unpaid_rewards(store, &staking.token_address, &staking.owner, &unpaid)?;
+    }
+    if shared {
+        staking.reward_debts = reward_debts(&collection);
+    }
+    staking.last_claimed_timestamp = until;
+    if ended {
+        staking.is_paid = true;
+    }
+    COLLECTIONS.save(store, staking.token_address.clone(), &collection)?;
+    Ok(rewards)
+}
+
+// one bank send carrying all native rewards and a transfer per cw20 reward.
+fn reward_messages(recipient: &str, rewards: &[Asset]) -> StdResult<Vec<CosmosMsg>> {
+    let mut coins: Vec<Coin> = vec![];
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for reward in rewards.iter() {
+        match &reward.info {
+            AssetInfo::Native { denom } => coins.push(Coin {
+                denom: denom.clone(),
+                amount: reward.amount,
+            }),
+            AssetInfo::Cw20 { contract_addr } => messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract_addr.clone(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: recipient.to_string(),
+                    amount: reward.amount,
+                })?,
+                funds: vec![],
+            })),
+        }
+    }
+    if !coins.is_empty() {
+        coins.sort_by(|a, b| a.denom.cmp(&b.denom));
+        messages.insert(
+            0,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: recipient.to_string(),
+                amount: coins,
+            }),
+        );
+    }
+    Ok(messages)
+}
+
+// event attribute values may not be empty
+fn assets_to_string(assets: &[Asset]) -> String {
+    if assets.is_empty() {
+        return "none".to_string();
+    }
+    assets
+        .iter()
+        .map(|asset| asset.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+// convert the single fee denom config. fees collected so far are booked under the
+// unstake fee denom.
+pub fn migrate_legacy_config(store: &mut dyn Storage) -> Result<bool, ContractError> {
+    if CONFIG.load(store).is_ok() {
+        return Ok(false);
+    }
+    let legacy = LEGACY_CONFIG.load(store)?;
+    CONFIG.save(
+        store,
+        &Config {
+            owner: Some(legacy.owner),
+            unstake_fees: vec![legacy.unstake_fee.clone()],
+            listing_fee: None,
+            paused: false,
+            emergency: false,
+        },
+    )?;
+    if legacy.fee_collected > 0 {
+        FEES_COLLECTED.save(
+            store,
+            legacy.unstake_fee.denom,
+            &Uint128::from(legacy.fee_collected),
+        )?;
+    }
+    Ok(true)
+}
+
+// convert single reward collections into reward stream collections.
+pub fn migrate_legacy_collections(store: &mut dyn Storage) -> Result<u64, ContractError> {
+    // raw keys, the typed iterator would parse the legacy values
+    let addresses = COLLECTIONS
+        .keys_raw(store, None, None, Order::Ascending)
+        .map(String::from_utf8)
+        .collect::<Result<Vec<String>, _>>()
+        .map_err(StdError::from)?;
+    let mut migrated: u64 = 0;
+    for address in addresses {
+        if COLLECTIONS.load(store, address.clone()).is_ok() {
+            continue;
+        }
+        let legacy = LEGACY_COLLECTIONS.load(store, address.clone())?;
+        let collection = Collection::new(
+            vec![RewardStream {
+                asset: AssetInfo::Native {
+                    denom: legacy.reward.denom,
+                },
+                // the legacy rate keeps applying to existing stakings
+                schedules: vec![RewardSchedule {
+                    start: Timestamp::from_seconds(0),
+                    end: None,
+                    amount: legacy.reward.amount,
+                    cycle: legacy.cycle,
+                }],
+                pool_amount: Uint128::from(legacy.pool_amount),
+                reward_per_share: Decimal256::zero(),
+                debt_amount: Uint128::zero(),
+                accrued_amount: Decimal256::zero(),
+            }],
+            legacy.cycle,
+            legacy.is_whitelisted,
+            // legacy spots were never enforced, zero keeps the collection unlimited
+            if legacy.spots == 0 {
+                u64::MAX
+            } else {
+                legacy.spots
+            },
+            RewardMode::Linear,
+        );
+        COLLECTIONS.save(store, address, &collection)?;
+        migrated += 1;
+    }
+    Ok(migrated)
+}
+
+pub fn migrate_stakings(
+    mut deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let store = deps.branch().storage;
+    migrate_legacy_stakings(store, env.block.time, limit)
+}
+
+const DEFAULT_MIGRATION_LIMIT: u32 = 30;
+const MAX_MIGRATION_LIMIT: u32 = 100;
+
+// convert up to `limit` stakings from the vector based layout into the indexed staking map.
+// active stakings keep their start time, unstaked but unpaid ones are paid out from the pool
+// and owed when the pool can not cover them.
+pub fn migrate_legacy_stakings(
+    store: &mut dyn Storage,
+    now: Timestamp,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let mut limit = limit
+        .unwrap_or(DEFAULT_MIGRATION_LIMIT)
+        .min(MAX_MIGRATION_LIMIT) as usize;
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut owners: u64 = 0;
+    let mut migrated: u64 = 0;
+    while limit > 0 {
+        let Some((owner, mut legacy_stakings)) = LEGACY_STAKINGS
+            .range(store, None, None, Order::Ascending)
+            .next()
+            .transpose()?
+        else {
+            break;
+        };
+        // large owners are converted over several batches
+        let batch: Vec<LegacyStaking> = legacy_stakings
+            .drain(..limit.min(legacy_stakings.len()))
+            .collect();
+        limit -= batch.len();
+        for legacy in batch.iter() {
+            let mut collection = COLLECTIONS.load(store, legacy.token_address.clone())?;
+            if legacy.end_timestamp == Timestamp::from_nanos(0) {
+                let key = (legacy.token_address.clone(), legacy.token_id.clone());
+                if stakings().has(store, key.clone()) {
+                    continue;
+                }
+                let staking = Staking::new(
+                    owner.clone(),
+                    legacy.token_address.clone(),
+                    legacy.token_id.clone(),
+                    legacy.start_timestamp,
+                    false,
+                );
+                // what the staking accrued before the migration is owed from now on
+                update_reward_per_share(&mut collection, now)?;
+                let pending = pending_rewards(&collection, &staking, now)?;
+                for (i, amount) in pending.into_iter().enumerate() {
+                    let stream = &mut collection.rewards[i];
+                    stream.accrued_amount = stream
+                        .accrued_amount
+                        .checked_add(Decimal256::from_ratio(amount, 1u128))?;
+                }
+                stakings().save(store, key, &staking)?;
+                collection.staked += 1;
+                collection.total_shares = collection.total_shares.checked_add(Decimal::one())?;
+                migrated += 1;
+            } else if !legacy.is_paid
+                && !collection.rewards.is_empty()
+                && !collection.rewards[0].schedules.is_empty()
+            {
+                // legacy reward is paid at the converted legacy rate, capped at one cycle reward
+                let stream = &mut collection.rewards[0];
+                let legacy_amount = stream.schedules[0].amount;
+                let reward_amount = legacy_amount
+                    .checked_mul(Uint128::from(
+                        legacy.end_timestamp.seconds() - legacy.start_timestamp.seconds(),
+                    ))?
+                    .checked_div(Uint128::from(collection.cycle))?
+                    .min(legacy_amount);
+                let paid_amount = reward_amount.min(stream.pool_amount);
+                if !paid_amount.is_zero() {
+                    stream.pool_amount -= paid_amount;
+                    messages.extend(reward_messages(
+                        &owner,
+                        &[Asset {
+                            info: stream.asset.clone(),
+                            amount: paid_amount,
+                        }],
+                    )?);
+                }
+                if reward_amount > paid_amount {
+                    let unpaid = Asset {
+                        info: stream.asset.clone(),
+                        amount: reward_amount - paid_amount,
+                    };
+                    stream.debt_amount = stream.debt_amount.checked_add(unpaid.amount)?;
+                    add_unpaid_rewards(store, &legacy.token_address, &owner, &[unpaid])?;
+                }
+            }
+            COLLECTIONS.save(store, legacy.token_address.clone(), &collection)?;
+        }
+        if legacy_stakings.is_empty() {
+            LEGACY_STAKINGS.remove(store, owner);
+            owners += 1;
+        } else {
+            LEGACY_STAKINGS.save(store, owner, &legacy_stakings)?;
+        }
     }
+    Ok(Response::new()
+        .add_event(
+            Event::new("stakings_migrated")
+                .add_attribute("owners", owners.to_string())
+                .add_attribute("stakings", migrated.to_string())
+                .add_attribute("remaining", legacy_stakings_pending(store).to_string()),
+        )
+        .add_messages(messages))
+}
+
+// legacy stakings are not counted in the collection spots until they are converted.
+fn legacy_stakings_pending(store: &dyn Storage) -> bool {
+    !LEGACY_STAKINGS.is_empty(store)
+}
+
+// reject user actions while the contract is paused.
+fn check_not_paused(store: &dyn Storage) -> Result<(), ContractError> {
+    if CONFIG.load(store)?.paused {
+        return Err(ContractError::Paused {});
+    }
+    Ok(())
 }
 
 // check message sender is contract owner.
@@ -337,8 +2616,48 @@ pub fn check_contract_owner_only(
     store: &dyn Storage,
 ) -> Result<bool, ContractError> {
     let config_state = CONFIG.load(store)?;
-    if config_state.owner != info.sender.to_string() {
+    if config_state.owner != Some(info.sender.to_string()) {
         return Err(ContractError::Unauthorized {});
     }
     Ok(true)
 }
+
+// check message sender is the collection admin, a collection manager or contract owner.
+pub fn check_collection_admin(
+    info: MessageInfo,
+    store: &dyn Storage,
+    collection: &Collection,
+) -> Result<bool, ContractError> {
+    if collection.admin == Some(info.sender.to_string()) {
+        return Ok(true);
+    }
+    check_role(info, store, Role::CollectionManager)
+}
+
+// check message sender may take funds out of the collection reward pool: its admin, the
+// contract owner or a fee manager.
+pub fn check_pool_admin(
+    info: MessageInfo,
+    store: &dyn Storage,
+    collection: &Collection,
+) -> Result<bool, ContractError> {
+    if collection.admin == Some(info.sender.to_string()) {
+        return Ok(true);
+    }
+    check_role(info, store, Role::FeeManager)
+}
+
+// check message sender is contract owner or holds the given role.
+pub fn check_role(
+    info: MessageInfo,
+    store: &dyn Storage,
+    role: Role,
+) -> Result<bool, ContractError> {
+    let roles = ROLES
+        .may_load(store, info.sender.to_string())?
+        .unwrap_or_default();
+    if roles.contains(&role) {
+        return Ok(true);
+    }
+    check_contract_owner_only(info, store)
+}
@@ -1,41 +1,204 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Binary, Coin, Empty, Timestamp};
-use cw721::Cw721ReceiveMsg;
+use cosmwasm_std::{Binary, Coin, Decimal, Empty, Timestamp, Uint128};
+use cw20::Cw20ReceiveMsg;
+use cw_utils::Expiration;
 use serde::{Deserialize, Serialize};
 
+use crate::state::{
+    Asset, AssetInfo, FeeShare, LockTier, OperatorApproval, PenaltyCharge, PenaltyCurve,
+    RewardDebt, RewardMode, RewardStream, Role, UnderfundedPolicy,
+};
+
 #[cw_serde]
 pub struct InstantiateMsg {
-    pub unstake_fee: Coin,
+    pub unstake_fees: Vec<Coin>, // accepted early unstake fees, empty for no fee
 }
 
 #[cw_serde]
-pub enum ExecuteMsg {
+pub struct MigrateMsg {
+    pub limit: Option<u32>, // legacy stakings converted in the first batch
+}
+
+// two-step ownership transfer, in the style of cw-ownable
+#[cw_serde]
+pub enum OwnershipAction {
     TransferOwnership {
+        new_owner: String,
+        expiry: Option<Expiration>,
+    },
+    AcceptOwnership,
+    CancelOwnershipTransfer,
+    RenounceOwnership,
+}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    UpdateOwnership(OwnershipAction),
+    GrantRole {
         address: String,
+        role: Role,
+    },
+    RevokeRole {
+        address: String,
+        role: Role,
     },
     ChangeFee {
-        fee: Coin,
+        fees: Vec<Coin>,
+    },
+    SetCollectionUnstakeFees {
+        address: String,
+        fees: Option<Vec<Coin>>, // none uses the config unstake fees
     },
     WhitelistCollection {
         address: String,
-        reward: Coin,
+        rewards: Vec<Asset>, // reward per cycle for each asset
         cycle: u64,
         is_whitelisted: bool,
         spots: u64,
+        reward_mode: Option<RewardMode>,
+    },
+    AddRewardSchedule {
+        address: String,
+        asset: AssetInfo,
+        start: Timestamp,
+        end: Timestamp,
+        amount: Uint128, // reward per cycle while the schedule runs
+    },
+    SetListingFee {
+        fee: Option<Coin>,
+    },
+    // list a cw721 created by the sender, paying the listing fee and a native reward pool
+    RegisterCollection {
+        address: String,
+        rewards: Vec<Asset>,
+        cycle: u64,
+        spots: u64,
+        reward_mode: Option<RewardMode>,
+    },
+    SetCollectionAdmin {
+        address: String,
+        admin: Option<String>, // none leaves the collection to the contract owner
+    },
+    SetUnbondingDuration {
+        address: String,
+        duration: u64, // 0 returns nfts on unstake
+    },
+    SetUnderfundedPolicy {
+        address: String,
+        policy: UnderfundedPolicy,
+    },
+    SetUnstakePenalty {
+        address: String,
+        curve: PenaltyCurve,
+        charge: PenaltyCharge,
+    },
+    SetFeeRecycling {
+        address: String,
+        share: Decimal, // share of unstake fees paid into the reward pool
+    },
+    SetLockTiers {
+        address: String,
+        tiers: Vec<LockTier>,
+    },
+    SetWeights {
+        address: String,
+        token_weights: Vec<TokenWeight>,
+        trait_weights: Vec<TraitWeight>,
+        snapshot_on_stake: Option<bool>,
+    },
+    FreezeCollection {
+        address: String,
+        frozen: bool,
     },
     DepositCollectionReward {
         address: String,
     },
     ReceiveNft(UniversalNftReceiveMsg),
+    Receive(Cw20ReceiveMsg),
     Unstake {
-        index: u64,
+        collection: String,
+        token_id: String,
+        recipient: Option<String>, // defaults to the staking owner
     },
     ClaimReward {
-        index: u64,
+        collection: String,
+        token_id: String,
+        recipient: Option<String>, // defaults to the staking owner
+    },
+    ApproveOperator {
+        operator: String,
+        claim: bool,
+        unstake: bool,
+        set_recipient: bool,
+        expiry: Option<Expiration>,
+    },
+    RevokeOperator {
+        operator: String,
+    },
+    WithdrawCollectionReward {
+        address: String,
+        asset: Asset,
+    },
+    ReallocateCollectionReward {
+        from: String,
+        to: String,
+        asset: Asset,
+    },
+    SetPaused {
+        paused: bool,
+    },
+    SetEmergency {
+        emergency: bool,
+    },
+    // return a staked nft without fee or reward, while the emergency mode is on
+    EmergencyUnstake {
+        collection: String,
+        token_id: String,
+    },
+    WithdrawUnbonded {
+        owner: Option<String>, // defaults to the sender, operators withdraw for the owner
+        limit: Option<u32>,
     },
     WithdrawFee {
         fee: Coin,
     },
+    SetFeeDistribution {
+        shares: Vec<FeeShare>,
+    },
+    DistributeFees {},
+    MigrateStakings {
+        limit: Option<u32>,
+    },
+}
+
+// hook messages of cw20 Send
+#[cw_serde]
+pub enum ReceiveMsg {
+    DepositCollectionReward {
+        address: String,
+    },
+    // list a cw721 created by the cw20 sender with the sent tokens as reward pool, only
+    // while the listing is free
+    RegisterCollection {
+        address: String,
+        rewards: Vec<Asset>,
+        cycle: u64,
+        spots: u64,
+        reward_mode: Option<RewardMode>,
+    },
+}
+
+#[cw_serde]
+pub struct TokenWeight {
+    pub token_id: String,
+    pub weight: Decimal,
+}
+
+#[cw_serde]
+pub struct TraitWeight {
+    pub trait_type: String,
+    pub value: String,
+    pub weight: Decimal,
 }
 
 #[cw_serde]
@@ -43,36 +206,129 @@ pub enum ExecuteMsg {
 pub enum QueryMsg {
     #[returns(ConfigResponse)]
     GetConfig {},
+    #[returns(OwnershipResponse)]
+    GetOwnership {},
+    #[returns(Vec<RolesResponse>)]
+    GetRoles {},
     #[returns(Vec<CollectionResponse>)]
     GetCollections {},
     #[returns(Vec<StakingResponse>)]
     GetStakingsByOwner { owner: String },
+    #[returns(StakingResponse)]
+    GetStaking {
+        collection: String,
+        token_id: String,
+    },
+    #[returns(Vec<UnbondingResponse>)]
+    GetUnbonding { owner: String },
+    #[returns(Vec<OperatorResponse>)]
+    GetOperators { owner: String },
+    #[returns(Vec<UnpaidRewardsResponse>)]
+    GetUnpaidRewards { collection: String },
+    #[returns(Vec<FeeShare>)]
+    GetFeeDistribution {},
+    #[returns(UnstakeFeeResponse)]
+    GetUnstakeFee {
+        collection: String,
+        token_id: String,
+    },
 }
 
 // responses
 #[cw_serde]
 pub struct ConfigResponse {
-    pub owner: String,
-    pub unstake_fee: Coin,
-    pub fee_collected: u128,
+    pub owner: Option<String>,
+    pub unstake_fees: Vec<Coin>,
+    pub fees_collected: Vec<Coin>,
+    pub listing_fee: Option<Coin>,
+    pub paused: bool,
+    pub emergency: bool,
+}
+
+#[cw_serde]
+pub struct OwnershipResponse {
+    pub owner: Option<String>,
+    pub pending_owner: Option<String>,
+    pub pending_expiry: Option<Expiration>,
+}
+
+#[cw_serde]
+pub struct RolesResponse {
+    pub address: String,
+    pub roles: Vec<Role>,
 }
 
 #[cw_serde]
 pub struct CollectionResponse {
     pub address: String,
-    pub reward: Coin,
+    pub rewards: Vec<RewardStream>,
     pub cycle: u64,
     pub is_whitelisted: bool,
     pub spots: u64,
+    pub reward_mode: RewardMode,
+    pub staked: u64,
+    pub available_spots: u64,
+    pub is_frozen: bool,
+    pub lock_tiers: Vec<LockTier>,
+    pub snapshot_weights: bool,
+    pub underfunded_policy: UnderfundedPolicy,
+    pub admin: Option<String>,
+    pub unbonding_duration: u64,
+    pub unstake_fees: Option<Vec<Coin>>,
+    pub penalty_curve: PenaltyCurve,
+    pub penalty_charge: PenaltyCharge,
+    pub fee_recycle_share: Decimal,
+}
+
+#[cw_serde]
+pub struct UnbondingResponse {
+    pub token_address: String,
+    pub token_id: String,
+    pub release_timestamp: Timestamp,
+}
+
+#[cw_serde]
+pub struct OperatorResponse {
+    pub operator: String,
+    pub approval: OperatorApproval,
+}
+
+#[cw_serde]
+pub struct UnpaidRewardsResponse {
+    pub owner: String,
+    pub rewards: Vec<Asset>, // owed until the next reward deposit
+}
+
+#[cw_serde]
+pub struct UnstakeFeeResponse {
+    pub fees: Vec<Coin>,              // accepted fees, one of them is charged
+    pub reward_share: Decimal,        // share of the reward withheld
+    pub withheld_rewards: Vec<Asset>, // reward withheld at the current block time
 }
 
 #[cw_serde]
 pub struct StakingResponse {
+    pub owner: String,
     pub token_address: String, // nft collection ca
     pub token_id: String,
     pub start_timestamp: Timestamp,
     pub end_timestamp: Timestamp,
+    pub last_claimed_timestamp: Timestamp,
     pub is_paid: bool,
+    pub lock_tier: Option<LockTier>,
+    pub weight: Option<Decimal>,
+    pub shares: Decimal,
+    pub reward_debts: Vec<RewardDebt>,
+    pub release_timestamp: Option<Timestamp>,
+    pub recipient: Option<String>,
+    pub unbonding_duration: u64,
+}
+
+// options passed in the ReceiveNft msg
+#[cw_serde]
+#[derive(Default)]
+pub struct StakeMsg {
+    pub lock_duration: Option<u64>, // duration of one of the collection lock tiers
 }
 
 #[derive(Deserialize)]
@@ -84,6 +340,26 @@ pub struct UniversalNftInfoResponse {
     extension: Empty,
 }
 
+#[derive(Deserialize)]
+pub struct TraitNftInfoResponse {
+    pub token_uri: Option<String>,
+    #[serde(default)]
+    pub extension: Option<TraitExtension>,
+}
+
+// on-chain metadata attributes, other extension fields are ignored
+#[derive(Deserialize)]
+pub struct TraitExtension {
+    #[serde(default)]
+    pub attributes: Option<Vec<NftTrait>>,
+}
+
+#[derive(Deserialize)]
+pub struct NftTrait {
+    pub trait_type: String,
+    pub value: String,
+}
+
 #[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
 pub struct UniversalNftReceiveMsg {
     pub sender: String,
@@ -1,46 +1,198 @@
 use std::vec;
 
-use cosmwasm_std::{to_json_binary, Deps, Env, Order, QueryResponse};
+use cosmwasm_std::{
+    to_json_binary, Coin, Decimal, Deps, Env, Order, QueryResponse, StdResult, Timestamp,
+};
+use cw_storage_plus::Bound;
 
 use crate::{
     error::ContractError,
-    msg::{CollectionResponse, ConfigResponse, StakingResponse},
-    state::{Staking, COLLECTIONS, CONFIG, STAKINGS},
+    execute::{pending_rewards, unstake_fee, update_reward_per_share},
+    msg::{
+        CollectionResponse, ConfigResponse, OperatorResponse, OwnershipResponse, RolesResponse,
+        UnbondingResponse, UnpaidRewardsResponse, UnstakeFeeResponse,
+    },
+    state::{
+        stakings, Asset, Staking, COLLECTIONS, CONFIG, FEES_COLLECTED, FEE_DISTRIBUTION,
+        NOT_UNBONDING, OPERATORS, PENDING_OWNERSHIP, ROLES, UNPAID_REWARDS,
+    },
 };
 
 pub fn get_config(deps: Deps) -> Result<QueryResponse, ContractError> {
     let config_state = CONFIG.load(deps.storage)?;
+    let fees_collected: Vec<Coin> = FEES_COLLECTED
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(denom, amount)| Coin { denom, amount }))
+        .collect::<StdResult<_>>()?;
     Ok(to_json_binary(&ConfigResponse {
         owner: config_state.clone().owner,
-        unstake_fee: config_state.clone().unstake_fee,
-        fee_collected: config_state.clone().fee_collected,
+        unstake_fees: config_state.clone().unstake_fees,
+        fees_collected,
+        listing_fee: config_state.clone().listing_fee,
+        paused: config_state.paused,
+        emergency: config_state.emergency,
     })
     .unwrap())
 }
 
-pub fn get_collections(deps: Deps, env: Env) -> Result<QueryResponse, ContractError> {
+pub fn get_ownership(deps: Deps) -> Result<QueryResponse, ContractError> {
+    let config_state = CONFIG.load(deps.storage)?;
+    let pending = PENDING_OWNERSHIP.may_load(deps.storage)?;
+    Ok(to_json_binary(&OwnershipResponse {
+        owner: config_state.owner,
+        pending_owner: pending.clone().map(|pending| pending.new_owner),
+        pending_expiry: pending.and_then(|pending| pending.expiry),
+    })
+    .unwrap())
+}
+
+pub fn get_roles(deps: Deps) -> Result<QueryResponse, ContractError> {
+    let roles: Vec<RolesResponse> = ROLES
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(address, roles)| RolesResponse { address, roles }))
+        .collect::<StdResult<_>>()?;
+    Ok(to_json_binary(&roles).unwrap())
+}
+
+pub fn get_collections(deps: Deps, _env: Env) -> Result<QueryResponse, ContractError> {
     let mut collections: Vec<CollectionResponse> = vec![];
     for k in COLLECTIONS.keys(deps.storage, None, None, Order::Ascending) {
         let address = k.unwrap();
         let collection = COLLECTIONS.load(deps.storage, address.clone()).unwrap();
         collections.push(CollectionResponse {
             address: address.clone(),
-            reward: collection.reward,
+            rewards: collection.rewards.clone(),
             cycle: collection.cycle,
             is_whitelisted: collection.is_whitelisted,
             spots: collection.spots,
+            reward_mode: collection.reward_mode.clone(),
+            staked: collection.staked,
+            available_spots: collection.available_spots(),
+            is_frozen: collection.is_frozen(),
+            lock_tiers: collection.lock_tiers.clone(),
+            snapshot_weights: collection.snapshot_weights,
+            underfunded_policy: collection.underfunded_policy.clone(),
+            admin: collection.admin.clone(),
+            unbonding_duration: collection.unbonding_duration,
+            unstake_fees: collection.unstake_fees.clone(),
+            penalty_curve: collection.penalty_curve.clone(),
+            penalty_charge: collection.penalty_charge.clone(),
+            fee_recycle_share: collection.fee_recycle_share,
         });
     }
     Ok(to_json_binary(&collections).unwrap())
 }
 
 pub fn get_stakings_by_owner(deps: Deps, owner: String) -> Result<QueryResponse, ContractError> {
-    let stakings: Vec<Staking>;
-    let stakings_state = STAKINGS.may_load(deps.storage, owner).unwrap();
-    if stakings_state.is_some() {
-        stakings = stakings_state.unwrap();
-    } else {
-        stakings = vec![];
-    }
+    let stakings: Vec<Staking> = stakings()
+        .idx
+        .owner
+        .prefix(owner)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, staking)| staking))
+        .collect::<StdResult<_>>()?;
     Ok(to_json_binary(&stakings).unwrap())
 }
+
+pub fn get_unbonding(deps: Deps, owner: String) -> Result<QueryResponse, ContractError> {
+    // stakings that are not unbonding sort after every release time
+    let until = Bound::exclusive((NOT_UNBONDING, (String::new(), String::new())));
+    let unbonding: Vec<UnbondingResponse> = stakings()
+        .idx
+        .release
+        .sub_prefix(owner)
+        .range(deps.storage, None, Some(until), Order::Ascending)
+        .map(|item| {
+            item.map(|(_, staking)| UnbondingResponse {
+                token_address: staking.token_address,
+                token_id: staking.token_id,
+                release_timestamp: staking.release_timestamp.unwrap_or_default(),
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(to_json_binary(&unbonding).unwrap())
+}
+
+pub fn get_staking(
+    deps: Deps,
+    collection: String,
+    token_id: String,
+) -> Result<QueryResponse, ContractError> {
+    let staking = stakings()
+        .may_load(deps.storage, (collection, token_id))?
+        .ok_or(ContractError::NotStaked {})?;
+    Ok(to_json_binary(&staking).unwrap())
+}
+
+pub fn get_operators(deps: Deps, owner: String) -> Result<QueryResponse, ContractError> {
+    let operators: Vec<OperatorResponse> = OPERATORS
+        .prefix(owner)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(operator, approval)| OperatorResponse { operator, approval }))
+        .collect::<StdResult<_>>()?;
+    Ok(to_json_binary(&operators).unwrap())
+}
+
+pub fn get_unpaid_rewards(deps: Deps, collection: String) -> Result<QueryResponse, ContractError> {
+    // unpaid rewards are stored by asset, grouped by owner here
+    let owed =
+        UNPAID_REWARDS
+            .sub_prefix(collection)
+            .range(deps.storage, None, None, Order::Ascending);
+    let mut unpaid: Vec<UnpaidRewardsResponse> = vec![];
+    for item in owed {
+        let ((_, owner), reward) = item?;
+        match unpaid.iter_mut().find(|unpaid| unpaid.owner == owner) {
+            Some(unpaid) => unpaid.rewards.push(reward),
+            None => unpaid.push(UnpaidRewardsResponse {
+                owner,
+                rewards: vec![reward],
+            }),
+        }
+    }
+    unpaid.sort_by(|a, b| a.owner.cmp(&b.owner));
+    Ok(to_json_binary(&unpaid).unwrap())
+}
+
+pub fn get_fee_distribution(deps: Deps) -> Result<QueryResponse, ContractError> {
+    let shares = FEE_DISTRIBUTION.may_load(deps.storage)?.unwrap_or_default();
+    Ok(to_json_binary(&shares).unwrap())
+}
+
+pub fn get_unstake_fee(
+    deps: Deps,
+    env: Env,
+    collection: String,
+    token_id: String,
+) -> Result<QueryResponse, ContractError> {
+    let config_state = CONFIG.load(deps.storage)?;
+    let staking = stakings()
+        .may_load(deps.storage, (collection.clone(), token_id))?
+        .ok_or(ContractError::NotStaked {})?;
+    if staking.end_timestamp != Timestamp::from_nanos(0) {
+        return Err(ContractError::AlreadyUnstaked {});
+    }
+    let mut collection = COLLECTIONS.load(deps.storage, collection)?;
+    let (fees, reward_share) = unstake_fee(&config_state, &collection, &staking, env.block.time)?;
+    update_reward_per_share(&mut collection, env.block.time)?;
+    let pending = pending_rewards(&collection, &staking, env.block.time)?;
+    let withheld_rewards: Vec<Asset> = collection
+        .rewards
+        .iter()
+        .zip(pending)
+        .map(|(stream, amount)| {
+            Ok(Asset {
+                info: stream.asset.clone(),
+                amount: amount
+                    .checked_multiply_ratio(reward_share.atomics(), Decimal::one().atomics())?,
+            })
+        })
+        .filter(|asset| asset.as_ref().map_or(true, |asset| !asset.amount.is_zero()))
+        .collect::<Result<_, ContractError>>()?;
+    Ok(to_json_binary(&UnstakeFeeResponse {
+        fees,
+        reward_share,
+        withheld_rewards,
+    })
+    .unwrap())
+}
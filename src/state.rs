@@ -1,86 +1,467 @@
-use cosmwasm_std::{coin, Coin, Timestamp};
+use cosmwasm_std::{Coin, Decimal, Decimal256, OverflowError, Timestamp, Uint128, Uint64};
 
-use cw_storage_plus::{Item, Map};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
+use cw_utils::Expiration;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::str::FromStr;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
-    pub owner: String,
-    pub unstake_fee: Coin,
-    pub fee_collected: u128,
+    pub owner: Option<String>,   // none once ownership is renounced
+    pub unstake_fees: Vec<Coin>, // accepted early unstake fees, one of them is charged
+    #[serde(default)]
+    pub listing_fee: Option<Coin>, // none closes permissionless registration
+    #[serde(default)]
+    pub paused: bool, // blocks staking, claims and deposits
+    #[serde(default)]
+    pub emergency: bool, // allows emergency unstakes
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingOwnership {
+    pub new_owner: String,
+    pub expiry: Option<Expiration>, // none never expires
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    FeeManager,        // fees, listing fee and reward pool withdrawals
+    CollectionManager, // whitelisting and any collection settings
+    Pauser,            // contract pause switch
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RewardMode {
+    #[default]
+    Linear, // reward accrues every second
+    PerCycle, // reward accrues only for completed cycles
+    Shared,   // reward per cycle is shared by all staked tokens by their shares
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum UnderfundedPolicy {
+    #[default]
+    Reject, // rewards the pool can not cover are rejected
+    PayAvailable, // pay what the pool holds and owe the remainder
+}
+
+// how the early unstake fee decays over the first cycle of a staking
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PenaltyCurve {
+    #[default]
+    Flat, // full fee until the cycle is over
+    Linear, // decays to zero at the end of the cycle
+    Stepwise {
+        steps: Vec<PenaltyStep>,
+    }, // fee share by elapsed time
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PenaltyStep {
+    pub elapsed: u64,     // seconds staked from which the step applies
+    pub percent: Decimal, // share of the fee charged
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PenaltyCharge {
+    #[default]
+    Coin, // one of the unstake fees attached to the unstake
+    RewardShare {
+        percent: Decimal,
+    }, // share of the reward paid on unstake
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FeeRecipient {
+    Address { address: String },              // e.g. the treasury
+    CollectionCreator { collection: String }, // the admin of the collection
+    RewardPool { collection: String },        // tops up the collection reward pool
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FeeShare {
+    pub recipient: FeeRecipient,
+    pub bps: u64, // share of the collected fees in basis points
+}
+
+// actions a staker allows an operator to take on their stakings
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OperatorApproval {
+    pub claim: bool,
+    pub unstake: bool,
+    pub set_recipient: bool, // rewards and nfts may be sent to another address
+    pub expiry: Option<Expiration>, // none never expires
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FrozenPeriod {
+    pub start: Timestamp,
+    pub end: Option<Timestamp>, // none while the collection is still frozen
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LockTier {
+    pub duration: u64,                       // lock duration in seconds
+    pub multiplier: Decimal,                 // reward multiplier
+    pub early_unstake_penalty: Option<Coin>, // none rejects early unstakes
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetInfo {
+    Native { denom: String },
+    Cw20 { contract_addr: String },
+}
+impl fmt::Display for AssetInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssetInfo::Native { denom } => write!(f, "{}", denom),
+            AssetInfo::Cw20 { contract_addr } => write!(f, "{}", contract_addr),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Asset {
+    pub info: AssetInfo,
+    pub amount: Uint128,
+}
+impl fmt::Display for Asset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.amount, self.info)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardSchedule {
+    pub start: Timestamp,
+    pub end: Option<Timestamp>, // none emits until the schedule is closed
+    pub amount: Uint128,        // reward amount per cycle
+    pub cycle: u64,             // cycle of the amount in seconds
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardStream {
+    pub asset: AssetInfo,               // native denom or cw20 token
+    pub schedules: Vec<RewardSchedule>, // emission schedules
+    pub pool_amount: Uint128,           // reward pool balance
+    #[serde(default)]
+    pub reward_per_share: Decimal256, // shared mode reward accumulator
+    #[serde(default)]
+    pub debt_amount: Uint128, // rewards owed to stakers while the pool was short
+    #[serde(default)]
+    pub accrued_amount: Decimal256, // rewards accrued by stakers and not settled yet
+}
+impl RewardStream {
+    // end the open ended schedule at `time`, dropping it if it has not started yet
+    pub fn close_open_schedule(&mut self, time: Timestamp) {
+        self.schedules
+            .retain(|schedule| schedule.end.is_some() || schedule.start < time);
+        for schedule in self.schedules.iter_mut() {
+            if schedule.end.is_none() {
+                schedule.end = Some(time);
+            }
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Collection {
-    pub reward: Coin,         // rewards coin denom & amount
-    pub cycle: u64,           // reward cycle
-    pub is_whitelisted: bool, // is whitelisted for staking
-    pub spots: u64,           // available spots
-    pub pool_amount: u128,    // Reward pool INJ amount
+    pub rewards: Vec<RewardStream>, // reward streams, one per asset
+    pub cycle: u64,                 // reward cycle
+    pub is_whitelisted: bool,       // is whitelisted for staking
+    pub spots: u64,                 // available spots
+    #[serde(default)]
+    pub reward_mode: RewardMode, // reward accrual mode
+    #[serde(default)]
+    pub staked: u64, // currently staked tokens
+    #[serde(default)]
+    pub frozen_periods: Vec<FrozenPeriod>, // periods without reward accrual
+    #[serde(default)]
+    pub lock_tiers: Vec<LockTier>, // lock-up tiers selectable at stake time
+    #[serde(default)]
+    pub snapshot_weights: bool, // snapshot token weight at stake time from on-chain traits
+    #[serde(default)]
+    pub total_shares: Decimal, // shares of all staked tokens
+    #[serde(default)]
+    pub reward_updated: Timestamp, // last accumulator update
+    #[serde(default)]
+    pub underfunded_policy: UnderfundedPolicy, // claims exceeding the reward pool
+    #[serde(default)]
+    pub admin: Option<String>, // manages the collection next to the contract owner
+    #[serde(default)]
+    pub unbonding_duration: u64, // seconds between unstake and nft release
+    #[serde(default)]
+    pub unstake_fees: Option<Vec<Coin>>, // overrides the config unstake fees
+    #[serde(default)]
+    pub penalty_curve: PenaltyCurve,
+    #[serde(default)]
+    pub penalty_charge: PenaltyCharge,
+    #[serde(default)]
+    pub fee_recycle_share: Decimal, // share of unstake fees paid into the reward pool
 }
 impl Collection {
+    pub fn available_spots(&self) -> u64 {
+        self.spots.saturating_sub(self.staked)
+    }
+    pub fn is_frozen(&self) -> bool {
+        self.frozen_periods
+            .last()
+            .is_some_and(|period| period.end.is_none())
+    }
+    // seconds between `from` and `to` during which the collection was not frozen
+    pub fn active_seconds(&self, from: Timestamp, to: Timestamp) -> u64 {
+        let (from, to) = (from.seconds(), to.seconds());
+        if to <= from {
+            return 0;
+        }
+        let frozen: u64 = self
+            .frozen_periods
+            .iter()
+            .map(|period| {
+                let start = period.start.seconds().max(from);
+                let end = period.end.map_or(to, |end| end.seconds()).min(to);
+                end.saturating_sub(start)
+            })
+            .sum();
+        (to - from).saturating_sub(frozen)
+    }
     pub fn default() -> Self {
         Collection {
-            reward: coin(0, "inj"),
+            rewards: vec![],
             cycle: 604_800, // 1 week = 7 * 24 * 60 * 60
             is_whitelisted: true,
             spots: 0,
-            pool_amount: 0,
+            reward_mode: RewardMode::Linear,
+            staked: 0,
+            frozen_periods: vec![],
+            lock_tiers: vec![],
+            snapshot_weights: false,
+            total_shares: Decimal::zero(),
+            reward_updated: Timestamp::from_seconds(0),
+            underfunded_policy: UnderfundedPolicy::Reject,
+            admin: None,
+            unbonding_duration: 0,
+            unstake_fees: None,
+            penalty_curve: PenaltyCurve::Flat,
+            penalty_charge: PenaltyCharge::Coin,
+            fee_recycle_share: Decimal::zero(),
         }
     }
     pub fn new(
-        reward: Coin,
+        rewards: Vec<RewardStream>,
         cycle: u64,
         is_whitelisted: bool,
         spots: u64,
-        pool_amount: u128,
+        reward_mode: RewardMode,
     ) -> Self {
         Collection {
-            reward,
+            rewards,
             cycle,
             is_whitelisted,
             spots,
-            pool_amount,
+            reward_mode,
+            staked: 0,
+            frozen_periods: vec![],
+            lock_tiers: vec![],
+            snapshot_weights: false,
+            total_shares: Decimal::zero(),
+            reward_updated: Timestamp::from_seconds(0),
+            underfunded_policy: UnderfundedPolicy::Reject,
+            admin: None,
+            unbonding_duration: 0,
+            unstake_fees: None,
+            penalty_curve: PenaltyCurve::Flat,
+            penalty_charge: PenaltyCharge::Coin,
+            fee_recycle_share: Decimal::zero(),
         }
     }
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardDebt {
+    pub asset: AssetInfo,
+    pub reward_per_share: Decimal256, // stream accumulator at the last settlement
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Staking {
+    pub owner: String,
     pub token_address: String, // nft collection ca
     pub token_id: String,
     pub start_timestamp: Timestamp,
     pub end_timestamp: Timestamp,
+    pub last_claimed_timestamp: Timestamp, // reward checkpoint
     pub is_paid: bool,
+    #[serde(default)]
+    pub lock_tier: Option<LockTier>, // lock-up tier snapshot
+    #[serde(default)]
+    pub weight: Option<Decimal>, // reward weight snapshot
+    #[serde(default)]
+    pub shares: Decimal, // weight times lock-up multiplier at stake time
+    #[serde(default)]
+    pub reward_debts: Vec<RewardDebt>, // shared mode reward checkpoints
+    #[serde(default)]
+    pub release_timestamp: Option<Timestamp>, // nft release time once unstaked and unbonding
+    #[serde(default)]
+    pub recipient: Option<String>, // nft recipient after unbonding, none for the owner
+    #[serde(default)]
+    pub unbonding_duration: u64, // collection unbonding duration at stake time
 }
 impl Staking {
     pub fn default() -> Self {
         Staking {
+            owner: String::from_str("").unwrap(),
             token_address: String::from_str("").unwrap(),
             token_id: String::from_str("").unwrap(),
             start_timestamp: Timestamp::from_seconds(0),
             end_timestamp: Timestamp::from_seconds(0),
+            last_claimed_timestamp: Timestamp::from_seconds(0),
             is_paid: false,
+            lock_tier: None,
+            weight: None,
+            shares: Decimal::zero(),
+            reward_debts: vec![],
+            release_timestamp: None,
+            recipient: None,
+            unbonding_duration: 0,
         }
     }
     pub fn new(
+        owner: String,
         token_address: String,
         token_id: String,
         start_timestamp: Timestamp,
         is_paid: bool,
     ) -> Self {
         Staking {
+            owner,
             token_address,
             token_id,
             start_timestamp,
             end_timestamp: Timestamp::from_seconds(0),
+            last_claimed_timestamp: start_timestamp,
             is_paid,
+            lock_tier: None,
+            weight: None,
+            shares: Decimal::one(),
+            reward_debts: vec![],
+            release_timestamp: None,
+            recipient: None,
+            unbonding_duration: 0,
         }
     }
+    pub fn unlock_timestamp(&self) -> Result<Option<Timestamp>, OverflowError> {
+        self.lock_tier
+            .as_ref()
+            .map(|tier| checked_plus_seconds(self.start_timestamp, tier.duration))
+            .transpose()
+    }
+}
+
+// `time` moved forward by `seconds`, failing instead of overflowing
+pub fn checked_plus_seconds(time: Timestamp, seconds: u64) -> Result<Timestamp, OverflowError> {
+    let nanos = Uint64::from(seconds)
+        .checked_mul(Uint64::from(1_000_000_000u64))?
+        .checked_add(Uint64::from(time.nanos()))?;
+    Ok(Timestamp::from_nanos(nanos.u64()))
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
+pub const PENDING_OWNERSHIP: Item<PendingOwnership> = Item::new("pending_ownership");
+// collected fees by denom
+pub const FEES_COLLECTED: Map<String, Uint128> = Map::new("fees_collected");
+// recipients the collected fees are distributed to
+pub const FEE_DISTRIBUTION: Item<Vec<FeeShare>> = Item::new("fee_distribution");
+// operator approvals by staker and operator
+pub const OPERATORS: Map<(String, String), OperatorApproval> = Map::new("operators");
+// roles granted by the owner, by address
+pub const ROLES: Map<String, Vec<Role>> = Map::new("roles");
 pub const COLLECTIONS: Map<String, Collection> = Map::new("collections");
-pub const STAKINGS: Map<String, Vec<Staking>> = Map::new("stakings");
+// reward weight by (token_address, token_id)
+pub const TOKEN_WEIGHTS: Map<(String, String), Decimal> = Map::new("token_weights");
+// reward weight by (token_address, trait_type, trait_value)
+pub const TRAIT_WEIGHTS: Map<(String, String, String), Decimal> = Map::new("trait_weights");
+// rewards owed by (token_address, asset, owner), paid out on the next deposit of the asset
+pub const UNPAID_REWARDS: Map<(String, String, String), Asset> = Map::new("unpaid_rewards");
+
+// release index key of stakings that are not unbonding, sorting after every release time
+pub const NOT_UNBONDING: u64 = u64::MAX;
+
+pub struct StakingIndexes<'a> {
+    pub owner: MultiIndex<'a, String, Staking, (String, String)>,
+    pub release: MultiIndex<'a, (String, u64), Staking, (String, String)>,
+}
+impl<'a> IndexList<Staking> for StakingIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Staking>> + '_> {
+        let v: Vec<&dyn Index<Staking>> = vec![&self.owner, &self.release];
+        Box::new(v.into_iter())
+    }
+}
+
+// stakings keyed by (token_address, token_id), indexed by owner and by owner and release
+// time in nanos
+pub fn stakings<'a>() -> IndexedMap<'a, (String, String), Staking, StakingIndexes<'a>> {
+    let indexes = StakingIndexes {
+        owner: MultiIndex::new(
+            |_pk, staking: &Staking| staking.owner.clone(),
+            "staking_positions",
+            "staking_positions__owner",
+        ),
+        release: MultiIndex::new(
+            |_pk, staking: &Staking| {
+                (
+                    staking.owner.clone(),
+                    staking
+                        .release_timestamp
+                        .map_or(NOT_UNBONDING, |release| release.nanos()),
+                )
+            },
+            "staking_positions",
+            "staking_positions__release",
+        ),
+    };
+    IndexedMap::new("staking_positions", indexes)
+}
+
+// single fee denom config record, kept for migration only
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyConfig {
+    pub owner: String,
+    pub unstake_fee: Coin,
+    pub fee_collected: u128,
+}
+
+pub const LEGACY_CONFIG: Item<LegacyConfig> = Item::new("config");
+
+// staking record of the vector based layout, kept for migration only
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyStaking {
+    pub token_address: String,
+    pub token_id: String,
+    pub start_timestamp: Timestamp,
+    pub end_timestamp: Timestamp,
+    pub is_paid: bool,
+}
+
+pub const LEGACY_STAKINGS: Map<String, Vec<LegacyStaking>> = Map::new("stakings");
+
+// single reward collection record, kept for migration only
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyCollection {
+    pub reward: Coin,
+    pub cycle: u64,
+    pub is_whitelisted: bool,
+    pub spots: u64,
+    pub pool_amount: u128,
+}
+
+pub const LEGACY_COLLECTIONS: Map<String, LegacyCollection> = Map::new("collections");
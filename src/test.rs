@@ -1,16 +1,28 @@
 #[cfg(test)]
 mod tests {
-    use cosmwasm_std::{coin, to_json_binary, Addr, Binary, CosmosMsg, Empty, Timestamp};
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{
+        coin, to_json_binary, Addr, Binary, Decimal, Decimal256, Empty, Timestamp, Uint128,
+    };
     use cw_multi_test::{App, Contract, ContractWrapper, Executor};
-    use schemars::JsonSchema;
+    use cw_utils::Expiration;
 
     use crate::{
-        contract::{execute, instantiate, query},
+        contract::{execute, instantiate, migrate, query},
+        error::ContractError,
         msg::{
-            CollectionResponse, CollectionTokensResponse, ConfigResponse, ExecuteMsg,
-            InstantiateMsg, QueryMsg, StakingResponse, StakingStateResponse,
+            CollectionResponse, ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg,
+            OperatorResponse, OwnershipAction, OwnershipResponse, QueryMsg, ReceiveMsg,
+            RolesResponse, StakeMsg, StakingResponse, TokenWeight, TraitWeight, UnbondingResponse,
+            UniversalNftReceiveMsg, UnpaidRewardsResponse, UnstakeFeeResponse,
+        },
+        state::{
+            stakings, Asset, AssetInfo, FeeRecipient, FeeShare, LegacyCollection, LegacyConfig,
+            LegacyStaking, LockTier, OperatorApproval, PenaltyCharge, PenaltyCurve, PenaltyStep,
+            RewardMode, RewardSchedule, RewardStream, Role, Staking, UnderfundedPolicy,
+            COLLECTIONS, CONFIG, FEES_COLLECTED, LEGACY_COLLECTIONS, LEGACY_CONFIG,
+            LEGACY_STAKINGS, UNPAID_REWARDS,
         },
-        state::Staking,
     };
 
     #[test]
@@ -41,7 +53,7 @@ mod tests {
                 token_uri: Some("token_uri".to_owned()),
                 extension: Empty {},
             },
-            &vec![],
+            &[],
         );
         let _ = app.execute_contract(
             owner.clone(),
@@ -52,14 +64,16 @@ mod tests {
                 token_uri: Some("token_uri".to_owned()),
                 extension: Empty {},
             },
-            &vec![],
+            &[],
         );
         let staking_code_id = app.store_code(staking_contract());
         let staking_contract_address = app
             .instantiate_contract(
                 staking_code_id,
                 owner.clone(),
-                &InstantiateMsg {},
+                &InstantiateMsg {
+                    unstake_fees: vec![],
+                },
                 &[],
                 "deploy staking contract",
                 None,
@@ -69,19 +83,20 @@ mod tests {
             .wrap()
             .query_wasm_smart(staking_contract_address.clone(), &QueryMsg::GetConfig {})
             .unwrap();
-        assert_eq!(resp.owner, "owner");
+        assert_eq!(resp.owner, Some("owner".to_owned()));
 
         app.execute_contract(
             owner.clone(),
             staking_contract_address.clone(),
             &ExecuteMsg::WhitelistCollection {
                 address: cw721_base_contract_address.clone().to_string(),
-                reward: coin(10, "inj"),
+                rewards: vec![native_asset(10, "inj")],
                 cycle: 604_800,
                 is_whitelisted: true,
                 spots: 1000,
+                reward_mode: None,
             },
-            &vec![],
+            &[],
         )
         .unwrap();
 
@@ -96,31 +111,62 @@ mod tests {
             resp,
             vec![CollectionResponse {
                 address: cw721_base_contract_address.clone().to_string(),
-                reward: coin(10, "inj"),
+                rewards: vec![RewardStream {
+                    asset: AssetInfo::Native {
+                        denom: "inj".to_owned(),
+                    },
+                    schedules: vec![RewardSchedule {
+                        start: app.block_info().time,
+                        end: None,
+                        amount: Uint128::new(10),
+                        cycle: 604_800,
+                    }],
+                    pool_amount: Uint128::zero(),
+                    reward_per_share: Decimal256::zero(),
+                    debt_amount: Uint128::zero(),
+                    accrued_amount: Decimal256::zero(),
+                }],
                 cycle: 604_800,
                 is_whitelisted: true,
                 spots: 1000,
-                name: "CW721 Base".into(),
-                symbol: "CWB".into(),
+                reward_mode: RewardMode::Linear,
+                available_spots: 1000,
+                is_frozen: false,
+                lock_tiers: vec![],
+                snapshot_weights: false,
+                underfunded_policy: UnderfundedPolicy::Reject,
+                admin: None,
+                unbonding_duration: 0,
+                unstake_fees: None,
+                penalty_curve: PenaltyCurve::Flat,
+                penalty_charge: PenaltyCharge::Coin,
+                fee_recycle_share: Decimal::zero(),
                 staked: 0,
-                num_tokens: 2
             }]
         );
 
         app.execute_contract(
             owner.clone(),
             staking_contract_address.clone(),
-            &ExecuteMsg::TransferOwnership {
-                address: "new_owner".to_owned(),
-            },
-            &vec![],
+            &ExecuteMsg::UpdateOwnership(OwnershipAction::TransferOwnership {
+                new_owner: "new_owner".to_owned(),
+                expiry: None,
+            }),
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            Addr::unchecked("new_owner"),
+            staking_contract_address.clone(),
+            &ExecuteMsg::UpdateOwnership(OwnershipAction::AcceptOwnership),
+            &[],
         )
         .unwrap();
         let resp: ConfigResponse = app
             .wrap()
             .query_wasm_smart(staking_contract_address.clone(), &QueryMsg::GetConfig {})
             .unwrap();
-        assert_eq!(resp.owner, "new_owner");
+        assert_eq!(resp.owner, Some("new_owner".to_owned()));
 
         let _ = app
             .execute_contract(
@@ -129,33 +175,11 @@ mod tests {
                 &cw721_base::ExecuteMsg::<Empty, Empty>::SendNft {
                     contract: staking_contract_address.clone().to_string(),
                     token_id: "0".to_owned(),
-                    msg: to_json_binary(&"").unwrap(),
-                },
-                &vec![],
-            )
-            .unwrap();
-
-        let resp: Vec<CollectionTokensResponse> = app
-            .wrap()
-            .query_wasm_smart(
-                staking_contract_address.clone().to_string(),
-                &QueryMsg::GetAllCollectionTokensByOwner {
-                    owner: owner.clone().to_string(),
+                    msg: to_json_binary(&StakeMsg::default()).unwrap(),
                 },
+                &[],
             )
             .unwrap();
-        assert_eq!(resp.len(), 1);
-        assert_eq!(
-            resp[0].token_address,
-            cw721_base_contract_address.clone().to_string()
-        );
-        assert_eq!(resp[0].tokens.len(), 2);
-        assert_eq!(resp[0].tokens[0].token_uri, Some("token_uri".to_owned()));
-        assert_eq!(resp[0].tokens[0].token_id, "1".to_owned());
-        assert_eq!(resp[0].tokens[0].staking_state, None);
-        assert_eq!(resp[0].tokens[1].token_uri, Some("token_uri".to_owned()));
-        assert_eq!(resp[0].tokens[1].token_id, "0".to_owned());
-        assert_eq!(resp[0].tokens[1].clone().staking_state.unwrap().index, 0);
 
         let resp: Vec<StakingResponse> = app
             .wrap()
@@ -167,6 +191,7 @@ mod tests {
             )
             .unwrap();
         assert_eq!(resp.len(), 1);
+        assert_eq!(resp[0].owner, owner.clone().to_string());
         assert_eq!(
             resp[0].token_address,
             cw721_base_contract_address.clone().to_string()
@@ -174,7 +199,7 @@ mod tests {
         assert_eq!(resp[0].token_id, "0".to_owned());
         assert_ne!(resp[0].start_timestamp, Timestamp::from_seconds(0));
         assert_eq!(resp[0].end_timestamp, Timestamp::from_seconds(0));
-        assert_eq!(resp[0].is_paid, false);
+        assert!(!resp[0].is_paid);
 
         let resp: cw721::OwnerOfResponse = app
             .wrap()
@@ -188,15 +213,47 @@ mod tests {
             .unwrap();
         assert_eq!(resp.owner, staking_contract_address.clone().to_string());
 
+        // rewards can be claimed while the nft is still staked
+        let _ = app
+            .execute_contract(
+                owner.clone(),
+                staking_contract_address.clone(),
+                &ExecuteMsg::ClaimReward {
+                    collection: cw721_base_contract_address.clone().to_string(),
+                    token_id: "0".to_owned(),
+                    recipient: None,
+                },
+                &[],
+            )
+            .unwrap();
+
+        let resp: Vec<StakingResponse> = app
+            .wrap()
+            .query_wasm_smart(
+                staking_contract_address.clone(),
+                &QueryMsg::GetStakingsByOwner {
+                    owner: owner.clone().to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(resp[0].end_timestamp, Timestamp::from_seconds(0));
+        assert_eq!(resp[0].last_claimed_timestamp, resp[0].start_timestamp);
+        assert!(!resp[0].is_paid);
+
         let _ = app
             .execute_contract(
                 owner.clone(),
                 staking_contract_address.clone(),
-                &ExecuteMsg::Unstake { index: 0 },
-                &vec![],
+                &ExecuteMsg::Unstake {
+                    collection: cw721_base_contract_address.clone().to_string(),
+                    token_id: "0".to_owned(),
+                    recipient: None,
+                },
+                &[],
             )
             .unwrap();
 
+        // unstake settles the remaining reward and releases the position
         let resp: Vec<Staking> = app
             .wrap()
             .query_wasm_smart(
@@ -206,14 +263,7 @@ mod tests {
                 },
             )
             .unwrap();
-        assert_eq!(
-            resp[0].token_address,
-            cw721_base_contract_address.clone().to_string()
-        );
-        assert_eq!(resp[0].token_id, "0".to_owned());
-        assert_ne!(resp[0].start_timestamp, Timestamp::from_seconds(0));
-        assert_ne!(resp[0].end_timestamp, Timestamp::from_seconds(0));
-        assert_eq!(resp[0].is_paid, false);
+        assert_eq!(resp.len(), 0);
 
         let resp: cw721::OwnerOfResponse = app
             .wrap()
@@ -227,36 +277,2864 @@ mod tests {
             .unwrap();
         assert_eq!(resp.owner, owner.clone().to_string());
 
-        let _ = app
+        let err = app
             .execute_contract(
                 owner.clone(),
                 staking_contract_address.clone(),
-                &ExecuteMsg::ClaimReward { index: 0 },
-                &vec![],
+                &ExecuteMsg::ClaimReward {
+                    collection: cw721_base_contract_address.clone().to_string(),
+                    token_id: "0".to_owned(),
+                    recipient: None,
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::NotStaked {}.to_string()
+        );
+    }
+
+    #[test]
+    fn test_unstake_empty_pool() {
+        let (mut app, owner, cw721_address, staking_address) = setup();
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::WithdrawCollectionReward {
+                address: cw721_address.to_string(),
+                asset: native_asset(100_000, "inj"),
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            owner.clone(),
+            cw721_address.clone(),
+            &cw721_base::ExecuteMsg::<Empty, Empty>::SendNft {
+                contract: staking_address.to_string(),
+                token_id: "0".to_owned(),
+                msg: Binary::default(),
+            },
+            &[],
+        )
+        .unwrap();
+        app.update_block(|block| block.time = block.time.plus_seconds(604_800));
+        // the nft is released and the reward the pool can not cover is owed
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::Unstake {
+                collection: cw721_address.to_string(),
+                token_id: "0".to_owned(),
+                recipient: None,
+            },
+            &[],
+        )
+        .unwrap();
+        let resp: cw721::OwnerOfResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw721_address.clone(),
+                &cw721::Cw721QueryMsg::OwnerOf {
+                    token_id: "0".to_owned(),
+                    include_expired: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(resp.owner, owner.to_string());
+        let resp: Vec<UnpaidRewardsResponse> = app
+            .wrap()
+            .query_wasm_smart(
+                staking_address.clone(),
+                &QueryMsg::GetUnpaidRewards {
+                    collection: cw721_address.to_string(),
+                },
             )
             .unwrap();
+        assert_eq!(
+            resp,
+            vec![UnpaidRewardsResponse {
+                owner: owner.to_string(),
+                rewards: vec![native_asset(10, "inj")],
+            }]
+        );
+    }
 
-        let resp: Vec<StakingResponse> = app
+    #[test]
+    fn test_spots() {
+        let (mut app, owner, cw721_address, staking_address) = setup();
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::WhitelistCollection {
+                address: cw721_address.to_string(),
+                rewards: vec![native_asset(10, "inj")],
+                cycle: 604_800,
+                is_whitelisted: true,
+                spots: 1,
+                reward_mode: None,
+            },
+            &[],
+        )
+        .unwrap();
+        let stake_msg = |token_id: &str| cw721_base::ExecuteMsg::<Empty, Empty>::SendNft {
+            contract: staking_address.to_string(),
+            token_id: token_id.to_owned(),
+            msg: Binary::default(),
+        };
+        app.execute_contract(owner.clone(), cw721_address.clone(), &stake_msg("0"), &[])
+            .unwrap();
+        let err = app
+            .execute_contract(owner.clone(), cw721_address.clone(), &stake_msg("1"), &[])
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::NoSpotsAvailable {}.to_string()
+        );
+        // unstaking frees the spot
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::Unstake {
+                collection: cw721_address.to_string(),
+                token_id: "0".to_owned(),
+                recipient: None,
+            },
+            &[coin(1, "inj")],
+        )
+        .unwrap();
+        app.execute_contract(owner.clone(), cw721_address.clone(), &stake_msg("1"), &[])
+            .unwrap();
+        let resp: Vec<CollectionResponse> = app
+            .wrap()
+            .query_wasm_smart(staking_address.clone(), &QueryMsg::GetCollections {})
+            .unwrap();
+        assert_eq!(resp[0].staked, 1);
+        assert_eq!(resp[0].available_spots, 0);
+    }
+
+    #[test]
+    fn test_freeze_and_delist() {
+        let (mut app, owner, cw721_address, staking_address) = setup();
+        let stake_msg = |token_id: &str| cw721_base::ExecuteMsg::<Empty, Empty>::SendNft {
+            contract: staking_address.to_string(),
+            token_id: token_id.to_owned(),
+            msg: Binary::default(),
+        };
+        let claim_msg = ExecuteMsg::ClaimReward {
+            collection: cw721_address.to_string(),
+            token_id: "0".to_owned(),
+            recipient: None,
+        };
+        let freeze_msg = |frozen: bool| ExecuteMsg::FreezeCollection {
+            address: cw721_address.to_string(),
+            frozen,
+        };
+        app.execute_contract(owner.clone(), cw721_address.clone(), &stake_msg("0"), &[])
+            .unwrap();
+        app.update_block(|block| block.time = block.time.plus_seconds(302_400));
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &freeze_msg(true),
+            &[],
+        )
+        .unwrap();
+        let err = app
+            .execute_contract(owner.clone(), cw721_address.clone(), &stake_msg("1"), &[])
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::CollectionFrozen {}.to_string()
+        );
+        // no reward accrues while frozen
+        app.update_block(|block| block.time = block.time.plus_seconds(604_800));
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &freeze_msg(false),
+            &[],
+        )
+        .unwrap();
+        app.update_block(|block| block.time = block.time.plus_seconds(302_400));
+        app.execute_contract(owner.clone(), staking_address.clone(), &claim_msg, &[])
+            .unwrap();
+        let balance = app.wrap().query_balance(owner.clone(), "inj").unwrap();
+        assert_eq!(balance.amount.u128(), 1_000_000 - 100_000 + 10);
+
+        // delisted collections take no new stakings, existing ones keep claiming
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::WhitelistCollection {
+                address: cw721_address.to_string(),
+                rewards: vec![native_asset(10, "inj")],
+                cycle: 604_800,
+                is_whitelisted: false,
+                spots: 1000,
+                reward_mode: None,
+            },
+            &[],
+        )
+        .unwrap();
+        let err = app
+            .execute_contract(owner.clone(), cw721_address.clone(), &stake_msg("1"), &[])
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::NotWhitelisted {}.to_string()
+        );
+        app.update_block(|block| block.time = block.time.plus_seconds(604_800));
+        app.execute_contract(owner.clone(), staking_address.clone(), &claim_msg, &[])
+            .unwrap();
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::Unstake {
+                collection: cw721_address.to_string(),
+                token_id: "0".to_owned(),
+                recipient: None,
+            },
+            &[],
+        )
+        .unwrap();
+        let balance = app.wrap().query_balance(owner.clone(), "inj").unwrap();
+        assert_eq!(balance.amount.u128(), 1_000_000 - 100_000 + 20);
+        let resp: cw721::OwnerOfResponse = app
             .wrap()
             .query_wasm_smart(
-                staking_contract_address.clone(),
-                &QueryMsg::GetStakingsByOwner {
-                    owner: owner.clone().to_string(),
+                cw721_address.clone(),
+                &cw721::Cw721QueryMsg::OwnerOf {
+                    token_id: "0".to_owned(),
+                    include_expired: None,
                 },
             )
             .unwrap();
+        assert_eq!(resp.owner, owner.to_string());
+    }
+
+    #[test]
+    fn test_lock_tiers() {
+        let (mut app, owner, cw721_address, staking_address) = setup();
+        // lock durations are bounded so the unlock time can not overflow
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                staking_address.clone(),
+                &ExecuteMsg::SetLockTiers {
+                    address: cw721_address.to_string(),
+                    tiers: vec![LockTier {
+                        duration: u64::MAX,
+                        multiplier: Decimal::percent(200),
+                        early_unstake_penalty: None,
+                    }],
+                },
+                &[],
+            )
+            .unwrap_err();
         assert_eq!(
-            resp[0].token_address,
-            cw721_base_contract_address.clone().to_string()
+            err.root_cause().to_string(),
+            ContractError::InvalidLockTier {}.to_string()
         );
-        assert_eq!(resp[0].token_id, "0".to_owned());
-        assert_ne!(resp[0].start_timestamp, Timestamp::from_seconds(0));
-        assert_ne!(resp[0].end_timestamp, Timestamp::from_seconds(0));
-        assert_eq!(resp[0].is_paid, true);
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::SetLockTiers {
+                address: cw721_address.to_string(),
+                tiers: vec![LockTier {
+                    duration: 2_592_000, // 30 days
+                    multiplier: Decimal::percent(200),
+                    early_unstake_penalty: None,
+                }],
+            },
+            &[],
+        )
+        .unwrap();
+
+        // unknown lock durations are rejected
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                cw721_address.clone(),
+                &cw721_base::ExecuteMsg::<Empty, Empty>::SendNft {
+                    contract: staking_address.to_string(),
+                    token_id: "0".to_owned(),
+                    msg: to_json_binary(&StakeMsg {
+                        lock_duration: Some(1),
+                    })
+                    .unwrap(),
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::InvalidLockTier {}.to_string()
+        );
+
+        app.execute_contract(
+            owner.clone(),
+            cw721_address.clone(),
+            &cw721_base::ExecuteMsg::<Empty, Empty>::SendNft {
+                contract: staking_address.to_string(),
+                token_id: "0".to_owned(),
+                msg: to_json_binary(&StakeMsg {
+                    lock_duration: Some(2_592_000),
+                })
+                .unwrap(),
+            },
+            &[],
+        )
+        .unwrap();
+        let unstake_msg = ExecuteMsg::Unstake {
+            collection: cw721_address.to_string(),
+            token_id: "0".to_owned(),
+            recipient: None,
+        };
+        let err = app
+            .execute_contract(owner.clone(), staking_address.clone(), &unstake_msg, &[])
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::LockedUp { .. }
+        ));
+
+        // after the lock the doubled reward is settled on unstake
+        app.update_block(|block| block.time = block.time.plus_seconds(2_592_000));
+        app.execute_contract(owner.clone(), staking_address.clone(), &unstake_msg, &[])
+            .unwrap();
+        let balance = app.wrap().query_balance(owner.clone(), "inj").unwrap();
+        // 10inj * 30 days / 7 days = 42inj, doubled by the tier multiplier
+        assert_eq!(balance.amount.u128(), 1_000_000 - 100_000 + 84);
     }
 
-    fn staking_contract() -> Box<dyn Contract<Empty>> {
-        let contract = ContractWrapper::new(execute, instantiate, query);
+    #[test]
+    fn test_reward_modes() {
+        // balance gained after half a cycle, one and a half and two and a half cycles
+        for (reward_mode, gains) in [
+            (RewardMode::Linear, [5, 10, 10]),
+            (RewardMode::PerCycle, [0, 10, 10]),
+        ] {
+            let (mut app, owner, cw721_address, staking_address) = setup();
+            app.execute_contract(
+                owner.clone(),
+                staking_address.clone(),
+                &ExecuteMsg::WhitelistCollection {
+                    address: cw721_address.to_string(),
+                    rewards: vec![native_asset(10, "inj")],
+                    cycle: 604_800,
+                    is_whitelisted: true,
+                    spots: 1000,
+                    reward_mode: Some(reward_mode),
+                },
+                &[],
+            )
+            .unwrap();
+            app.execute_contract(
+                owner.clone(),
+                cw721_address.clone(),
+                &cw721_base::ExecuteMsg::<Empty, Empty>::SendNft {
+                    contract: staking_address.to_string(),
+                    token_id: "0".to_owned(),
+                    msg: Binary::default(),
+                },
+                &[],
+            )
+            .unwrap();
+            let mut balance = 1_000_000 - 100_000;
+            for (i, gain) in gains.into_iter().enumerate() {
+                let elapsed = if i == 0 { 302_400 } else { 604_800 };
+                app.update_block(|block| block.time = block.time.plus_seconds(elapsed));
+                // the last step unstakes, per cycle mode drops the partial cycle
+                let msg = if i == 2 {
+                    ExecuteMsg::Unstake {
+                        collection: cw721_address.to_string(),
+                        token_id: "0".to_owned(),
+                        recipient: None,
+                    }
+                } else {
+                    ExecuteMsg::ClaimReward {
+                        collection: cw721_address.to_string(),
+                        token_id: "0".to_owned(),
+                        recipient: None,
+                    }
+                };
+                app.execute_contract(owner.clone(), staking_address.clone(), &msg, &[])
+                    .unwrap();
+                balance += gain;
+                let resp = app.wrap().query_balance(owner.clone(), "inj").unwrap();
+                assert_eq!(resp.amount.u128(), balance);
+            }
+        }
+    }
+
+    #[test]
+    fn test_weights() {
+        for reward_mode in [RewardMode::Linear, RewardMode::PerCycle] {
+            let (mut app, owner, cw721_address, staking_address) = setup();
+            app.execute_contract(
+                owner.clone(),
+                staking_address.clone(),
+                &ExecuteMsg::WhitelistCollection {
+                    address: cw721_address.to_string(),
+                    rewards: vec![native_asset(10, "inj")],
+                    cycle: 604_800,
+                    is_whitelisted: true,
+                    spots: 1000,
+                    reward_mode: Some(reward_mode.clone()),
+                },
+                &[],
+            )
+            .unwrap();
+
+            // trait weights are only applied by stake time snapshots
+            let trait_weights = vec![TraitWeight {
+                trait_type: "rarity".to_owned(),
+                value: "legendary".to_owned(),
+                weight: Decimal::percent(300),
+            }];
+            let err = app
+                .execute_contract(
+                    owner.clone(),
+                    staking_address.clone(),
+                    &ExecuteMsg::SetWeights {
+                        address: cw721_address.to_string(),
+                        token_weights: vec![],
+                        trait_weights: trait_weights.clone(),
+                        snapshot_on_stake: None,
+                    },
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.root_cause().to_string(),
+                ContractError::TraitWeightsNeedSnapshot {}.to_string()
+            );
+
+            let set_token_weight = |app: &mut App, weight: u64| {
+                app.execute_contract(
+                    owner.clone(),
+                    staking_address.clone(),
+                    &ExecuteMsg::SetWeights {
+                        address: cw721_address.to_string(),
+                        token_weights: vec![TokenWeight {
+                            token_id: "0".to_owned(),
+                            weight: Decimal::percent(weight),
+                        }],
+                        trait_weights: vec![],
+                        snapshot_on_stake: None,
+                    },
+                    &[],
+                )
+                .unwrap();
+            };
+            set_token_weight(&mut app, 200);
+            app.execute_contract(
+                owner.clone(),
+                cw721_address.clone(),
+                &cw721_base::ExecuteMsg::<Empty, Empty>::SendNft {
+                    contract: staking_address.to_string(),
+                    token_id: "0".to_owned(),
+                    msg: Binary::default(),
+                },
+                &[],
+            )
+            .unwrap();
+            // a weight change settles the reward accrued at the old weight first
+            app.update_block(|block| block.time = block.time.plus_seconds(302_400));
+            set_token_weight(&mut app, 500);
+            let resp = app.wrap().query_balance(owner.clone(), "inj").unwrap();
+            let settled = match reward_mode {
+                RewardMode::PerCycle => 0,
+                _ => 10,
+            };
+            assert_eq!(resp.amount.u128(), 1_000_000 - 100_000 + settled);
+            let resp: StakingResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    staking_address.clone(),
+                    &QueryMsg::GetStaking {
+                        collection: cw721_address.to_string(),
+                        token_id: "0".to_owned(),
+                    },
+                )
+                .unwrap();
+            assert_eq!(resp.shares, Decimal::percent(500));
+            // the running per cycle reward stays reserved at the new weight
+            let err = app
+                .execute_contract(
+                    owner.clone(),
+                    staking_address.clone(),
+                    &ExecuteMsg::WithdrawCollectionReward {
+                        address: cw721_address.to_string(),
+                        asset: native_asset(100_000, "inj"),
+                    },
+                    &[],
+                )
+                .unwrap_err();
+            let available = match reward_mode {
+                RewardMode::PerCycle => 100_000 - 25,
+                _ => 100_000 - 10,
+            };
+            assert_eq!(
+                err.root_cause().to_string(),
+                ContractError::PoolFundsAllocated {
+                    available: Uint128::new(available)
+                }
+                .to_string()
+            );
+            app.update_block(|block| block.time = block.time.plus_seconds(302_400));
+            app.execute_contract(
+                owner.clone(),
+                staking_address.clone(),
+                &ExecuteMsg::ClaimReward {
+                    collection: cw721_address.to_string(),
+                    token_id: "0".to_owned(),
+                    recipient: None,
+                },
+                &[],
+            )
+            .unwrap();
+            // the running cycle is paid at the new weight in per cycle mode
+            let resp = app.wrap().query_balance(owner.clone(), "inj").unwrap();
+            let reward = match reward_mode {
+                RewardMode::PerCycle => 50,
+                _ => 10 + 25,
+            };
+            assert_eq!(resp.amount.u128(), 1_000_000 - 100_000 + reward);
+
+            app.execute_contract(
+                owner.clone(),
+                staking_address.clone(),
+                &ExecuteMsg::SetWeights {
+                    address: cw721_address.to_string(),
+                    token_weights: vec![],
+                    trait_weights,
+                    snapshot_on_stake: Some(true),
+                },
+                &[],
+            )
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_reward_schedules() {
+        let (mut app, owner, cw721_address, staking_address) = setup();
+        let start = app.block_info().time;
+        app.execute_contract(
+            owner.clone(),
+            cw721_address.clone(),
+            &cw721_base::ExecuteMsg::<Empty, Empty>::SendNft {
+                contract: staking_address.to_string(),
+                token_id: "0".to_owned(),
+                msg: Binary::default(),
+            },
+            &[],
+        )
+        .unwrap();
+
+        // schedules must not start in the past
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                staking_address.clone(),
+                &ExecuteMsg::AddRewardSchedule {
+                    address: cw721_address.to_string(),
+                    asset: AssetInfo::Native {
+                        denom: "inj".to_owned(),
+                    },
+                    start: start.minus_seconds(1),
+                    end: start.plus_seconds(604_800),
+                    amount: Uint128::new(70),
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::InvalidSchedule {}.to_string()
+        );
+
+        // a one week campaign starting after the first week
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::AddRewardSchedule {
+                address: cw721_address.to_string(),
+                asset: AssetInfo::Native {
+                    denom: "inj".to_owned(),
+                },
+                start: start.plus_seconds(604_800),
+                end: start.plus_seconds(1_209_600),
+                amount: Uint128::new(70),
+            },
+            &[],
+        )
+        .unwrap();
+
+        let claim_msg = ExecuteMsg::ClaimReward {
+            collection: cw721_address.to_string(),
+            token_id: "0".to_owned(),
+            recipient: None,
+        };
+        app.update_block(|block| block.time = block.time.plus_seconds(3 * 604_800));
+        app.execute_contract(owner.clone(), staking_address.clone(), &claim_msg, &[])
+            .unwrap();
+        let balance = app.wrap().query_balance(owner.clone(), "inj").unwrap();
+        // 3 weeks of base reward plus the campaign week
+        assert_eq!(balance.amount.u128(), 1_000_000 - 100_000 + 30 + 70);
+
+        // rate changes only apply from the time they are made
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::WhitelistCollection {
+                address: cw721_address.to_string(),
+                rewards: vec![native_asset(20, "inj")],
+                cycle: 604_800,
+                is_whitelisted: true,
+                spots: 1000,
+                reward_mode: None,
+            },
+            &[],
+        )
+        .unwrap();
+        app.update_block(|block| block.time = block.time.plus_seconds(604_800));
+        app.execute_contract(owner.clone(), staking_address.clone(), &claim_msg, &[])
+            .unwrap();
+        let balance = app.wrap().query_balance(owner.clone(), "inj").unwrap();
+        assert_eq!(balance.amount.u128(), 1_000_000 - 100_000 + 100 + 20);
+    }
+
+    #[test]
+    fn test_shared_rewards() {
+        let (mut app, owner, cw721_address, staking_address) = setup();
+        let whitelist_msg = |reward_mode| ExecuteMsg::WhitelistCollection {
+            address: cw721_address.to_string(),
+            rewards: vec![native_asset(70, "inj")],
+            cycle: 604_800,
+            is_whitelisted: true,
+            spots: 1000,
+            reward_mode: Some(reward_mode),
+        };
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &whitelist_msg(RewardMode::Shared),
+            &[],
+        )
+        .unwrap();
+        let stake = |app: &mut App, token_id: &str| {
+            app.execute_contract(
+                owner.clone(),
+                cw721_address.clone(),
+                &cw721_base::ExecuteMsg::<Empty, Empty>::SendNft {
+                    contract: staking_address.to_string(),
+                    token_id: token_id.to_owned(),
+                    msg: Binary::default(),
+                },
+                &[],
+            )
+            .unwrap();
+        };
+        stake(&mut app, "0");
+
+        // the shared mode can not be left while tokens are staked
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                staking_address.clone(),
+                &whitelist_msg(RewardMode::Linear),
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::RewardModeLocked {}.to_string()
+        );
+
+        app.update_block(|block| block.time = block.time.plus_seconds(604_800));
+        stake(&mut app, "1");
+        app.update_block(|block| block.time = block.time.plus_seconds(604_800));
+        for token_id in ["0", "1"] {
+            app.execute_contract(
+                owner.clone(),
+                staking_address.clone(),
+                &ExecuteMsg::ClaimReward {
+                    collection: cw721_address.to_string(),
+                    token_id: token_id.to_owned(),
+                    recipient: None,
+                },
+                &[],
+            )
+            .unwrap();
+        }
+        // token 0 earns the first week alone and half of the second one,
+        // token 1 the other half: the pool emits exactly 70inj per week
+        let balance = app.wrap().query_balance(owner.clone(), "inj").unwrap();
+        assert_eq!(balance.amount.u128(), 1_000_000 - 100_000 + 105 + 35);
+    }
+
+    #[test]
+    fn test_underfunded_pool() {
+        let (mut app, owner, cw721_address, staking_address) = setup();
+        // a week of rewards costs more than the 100_000inj pool
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::WhitelistCollection {
+                address: cw721_address.to_string(),
+                rewards: vec![native_asset(150_000, "inj")],
+                cycle: 604_800,
+                is_whitelisted: true,
+                spots: 1000,
+                reward_mode: None,
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            owner.clone(),
+            cw721_address.clone(),
+            &cw721_base::ExecuteMsg::<Empty, Empty>::SendNft {
+                contract: staking_address.to_string(),
+                token_id: "0".to_owned(),
+                msg: Binary::default(),
+            },
+            &[],
+        )
+        .unwrap();
+        app.update_block(|block| block.time = block.time.plus_seconds(604_800));
+        let claim_msg = ExecuteMsg::ClaimReward {
+            collection: cw721_address.to_string(),
+            token_id: "0".to_owned(),
+            recipient: None,
+        };
+        let err = app
+            .execute_contract(owner.clone(), staking_address.clone(), &claim_msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::NotEnoughRewardPool {}.to_string()
+        );
+
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::SetUnderfundedPolicy {
+                address: cw721_address.to_string(),
+                policy: UnderfundedPolicy::PayAvailable,
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(owner.clone(), staking_address.clone(), &claim_msg, &[])
+            .unwrap();
+        let balance = app.wrap().query_balance(owner.clone(), "inj").unwrap();
+        assert_eq!(balance.amount.u128(), 1_000_000);
+        let unpaid_query = QueryMsg::GetUnpaidRewards {
+            collection: cw721_address.to_string(),
+        };
+        let resp: Vec<UnpaidRewardsResponse> = app
+            .wrap()
+            .query_wasm_smart(staking_address.clone(), &unpaid_query)
+            .unwrap();
+        assert_eq!(
+            resp,
+            vec![UnpaidRewardsResponse {
+                owner: owner.to_string(),
+                rewards: vec![native_asset(50_000, "inj")],
+            }]
+        );
+
+        // the next deposit pays the owed remainder first
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::DepositCollectionReward {
+                address: cw721_address.to_string(),
+            },
+            &[coin(60_000, "inj")],
+        )
+        .unwrap();
+        let balance = app.wrap().query_balance(owner.clone(), "inj").unwrap();
+        assert_eq!(balance.amount.u128(), 1_000_000 - 60_000 + 50_000);
+        let resp: Vec<UnpaidRewardsResponse> = app
+            .wrap()
+            .query_wasm_smart(staking_address.clone(), &unpaid_query)
+            .unwrap();
+        assert!(resp.is_empty());
+    }
+
+    #[test]
+    fn test_withdraw_reward_pool() {
+        let (mut app, owner, cw721_address, staking_address) = setup();
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::WhitelistCollection {
+                address: "collection2".to_owned(),
+                rewards: vec![native_asset(10, "inj")],
+                cycle: 604_800,
+                is_whitelisted: true,
+                spots: 1000,
+                reward_mode: None,
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            owner.clone(),
+            cw721_address.clone(),
+            &cw721_base::ExecuteMsg::<Empty, Empty>::SendNft {
+                contract: staking_address.to_string(),
+                token_id: "0".to_owned(),
+                msg: Binary::default(),
+            },
+            &[],
+        )
+        .unwrap();
+        app.update_block(|block| block.time = block.time.plus_seconds(604_800));
+
+        // the 10inj accrued by the staking stay in the pool
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                staking_address.clone(),
+                &ExecuteMsg::WithdrawCollectionReward {
+                    address: cw721_address.to_string(),
+                    asset: native_asset(100_000, "inj"),
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::PoolFundsAllocated {
+                available: Uint128::new(99_990)
+            }
+            .to_string()
+        );
+
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::ReallocateCollectionReward {
+                from: cw721_address.to_string(),
+                to: "collection2".to_owned(),
+                asset: native_asset(90_000, "inj"),
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::WithdrawCollectionReward {
+                address: cw721_address.to_string(),
+                asset: native_asset(9_990, "inj"),
+            },
+            &[],
+        )
+        .unwrap();
+        let resp: Vec<CollectionResponse> = app
+            .wrap()
+            .query_wasm_smart(staking_address.clone(), &QueryMsg::GetCollections {})
+            .unwrap();
+        let pools: Vec<(String, Uint128)> = resp
+            .iter()
+            .map(|collection| {
+                (
+                    collection.address.clone(),
+                    collection.rewards[0].pool_amount,
+                )
+            })
+            .collect();
+        assert!(pools.contains(&(cw721_address.to_string(), Uint128::new(10))));
+        assert!(pools.contains(&("collection2".to_owned(), Uint128::new(90_000))));
+
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::ClaimReward {
+                collection: cw721_address.to_string(),
+                token_id: "0".to_owned(),
+                recipient: None,
+            },
+            &[],
+        )
+        .unwrap();
+        let balance = app.wrap().query_balance(owner.clone(), "inj").unwrap();
+        assert_eq!(balance.amount.u128(), 1_000_000 - 90_000);
+    }
+
+    #[test]
+    fn test_collection_admin() {
+        let (mut app, owner, cw721_address, staking_address) = setup();
+        let admin = Addr::unchecked("admin");
+        let whitelist_msg = |spots, is_whitelisted| ExecuteMsg::WhitelistCollection {
+            address: cw721_address.to_string(),
+            rewards: vec![native_asset(10, "inj")],
+            cycle: 604_800,
+            is_whitelisted,
+            spots,
+            reward_mode: None,
+        };
+        let err = app
+            .execute_contract(
+                admin.clone(),
+                staking_address.clone(),
+                &whitelist_msg(10, true),
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::Unauthorized {}.to_string()
+        );
+
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::SetCollectionAdmin {
+                address: cw721_address.to_string(),
+                admin: Some(admin.to_string()),
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            admin.clone(),
+            staking_address.clone(),
+            &whitelist_msg(10, true),
+            &[],
+        )
+        .unwrap();
+        // delisting stays with the contract owner
+        let err = app
+            .execute_contract(
+                admin.clone(),
+                staking_address.clone(),
+                &whitelist_msg(10, false),
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::Unauthorized {}.to_string()
+        );
+
+        app.execute_contract(
+            admin.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::WithdrawCollectionReward {
+                address: cw721_address.to_string(),
+                asset: native_asset(1_000, "inj"),
+            },
+            &[],
+        )
+        .unwrap();
+        let balance = app.wrap().query_balance(admin.clone(), "inj").unwrap();
+        assert_eq!(balance.amount.u128(), 1_000);
+        let resp: Vec<CollectionResponse> = app
+            .wrap()
+            .query_wasm_smart(staking_address.clone(), &QueryMsg::GetCollections {})
+            .unwrap();
+        assert_eq!(resp[0].spots, 10);
+        assert_eq!(resp[0].admin, Some(admin.to_string()));
+    }
+
+    #[test]
+    fn test_register_collection() {
+        let (mut app, owner, _, staking_address) = setup();
+        let cw721_base_code_id = app.store_code(cw721_base_contract());
+        let cw721_address = app
+            .instantiate_contract(
+                cw721_base_code_id,
+                owner.clone(),
+                &cw721_base::InstantiateMsg {
+                    name: "Other CW721".to_owned(),
+                    symbol: "OTH".to_owned(),
+                    minter: owner.to_string(),
+                },
+                &[],
+                "deploy cw721_base contract",
+                None,
+            )
+            .unwrap();
+        let register_msg = |address: &Addr| ExecuteMsg::RegisterCollection {
+            address: address.to_string(),
+            rewards: vec![native_asset(10, "inj")],
+            cycle: 604_800,
+            spots: 100,
+            reward_mode: None,
+        };
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                staking_address.clone(),
+                &register_msg(&cw721_address),
+                &[coin(1_100, "inj")],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::RegistrationClosed {}.to_string()
+        );
+
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::SetListingFee {
+                fee: Some(coin(100, "inj")),
+            },
+            &[],
+        )
+        .unwrap();
+        // only the creator of the cw721 can list it
+        let err = app
+            .execute_contract(
+                Addr::unchecked("other"),
+                staking_address.clone(),
+                &register_msg(&cw721_address),
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::Unauthorized {}.to_string()
+        );
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                staking_address.clone(),
+                &register_msg(&staking_address),
+                &[coin(1_100, "inj")],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::NotCw721 {}.to_string()
+        );
+
+        // an initial reward pool is required next to the listing fee
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                staking_address.clone(),
+                &register_msg(&cw721_address),
+                &[coin(100, "inj")],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::NoFunds {}.to_string()
+        );
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &register_msg(&cw721_address),
+            &[coin(1_100, "inj")],
+        )
+        .unwrap();
+        let resp: ConfigResponse = app
+            .wrap()
+            .query_wasm_smart(staking_address.clone(), &QueryMsg::GetConfig {})
+            .unwrap();
+        assert_eq!(resp.fees_collected, vec![coin(100, "inj")]);
+        let resp: Vec<CollectionResponse> = app
+            .wrap()
+            .query_wasm_smart(staking_address.clone(), &QueryMsg::GetCollections {})
+            .unwrap();
+        let collection = resp
+            .iter()
+            .find(|collection| collection.address == cw721_address.as_str())
+            .unwrap();
+        assert_eq!(collection.admin, Some(owner.to_string()));
+        assert_eq!(collection.rewards[0].pool_amount, Uint128::new(1_000));
+
+        // cw20 reward pools are deposited with the registration through the cw20 hook
+        let cw721_address = app
+            .instantiate_contract(
+                cw721_base_code_id,
+                owner.clone(),
+                &cw721_base::InstantiateMsg {
+                    name: "Token CW721".to_owned(),
+                    symbol: "TOK".to_owned(),
+                    minter: owner.to_string(),
+                },
+                &[],
+                "deploy cw721_base contract",
+                None,
+            )
+            .unwrap();
+        let cw20_code_id = app.store_code(cw20_base_contract());
+        let cw20_address = app
+            .instantiate_contract(
+                cw20_code_id,
+                owner.clone(),
+                &cw20_base::msg::InstantiateMsg {
+                    name: "Reward Token".to_owned(),
+                    symbol: "RWD".to_owned(),
+                    decimals: 6,
+                    initial_balances: vec![cw20::Cw20Coin {
+                        address: owner.to_string(),
+                        amount: Uint128::new(1_000_000),
+                    }],
+                    mint: None,
+                    marketing: None,
+                },
+                &[],
+                "deploy cw20_base contract",
+                None,
+            )
+            .unwrap();
+        let cw20_reward = AssetInfo::Cw20 {
+            contract_addr: cw20_address.to_string(),
+        };
+        let register_msg = ReceiveMsg::RegisterCollection {
+            address: cw721_address.to_string(),
+            rewards: vec![Asset {
+                info: cw20_reward.clone(),
+                amount: Uint128::new(10),
+            }],
+            cycle: 604_800,
+            spots: 100,
+            reward_mode: None,
+        };
+        let send_msg = cw20::Cw20ExecuteMsg::Send {
+            contract: staking_address.to_string(),
+            amount: Uint128::new(5_000),
+            msg: to_json_binary(&register_msg).unwrap(),
+        };
+        // the listing fee can not be attached to a cw20 send
+        let err = app
+            .execute_contract(owner.clone(), cw20_address.clone(), &send_msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::NotEnoughListingFee {}.to_string()
+        );
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::SetListingFee {
+                fee: Some(coin(0, "inj")),
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(owner.clone(), cw20_address.clone(), &send_msg, &[])
+            .unwrap();
+        let resp: Vec<CollectionResponse> = app
+            .wrap()
+            .query_wasm_smart(staking_address.clone(), &QueryMsg::GetCollections {})
+            .unwrap();
+        let collection = resp
+            .iter()
+            .find(|collection| collection.address == cw721_address.as_str())
+            .unwrap();
+        assert_eq!(collection.admin, Some(owner.to_string()));
+        assert_eq!(collection.rewards[0].asset, cw20_reward);
+        assert_eq!(collection.rewards[0].pool_amount, Uint128::new(5_000));
+    }
+
+    #[test]
+    fn test_ownership() {
+        let (mut app, owner, _, staking_address) = setup();
+        let new_owner = Addr::unchecked("new_owner");
+        let transfer_msg = ExecuteMsg::UpdateOwnership(OwnershipAction::TransferOwnership {
+            new_owner: new_owner.to_string(),
+            expiry: Some(Expiration::AtHeight(app.block_info().height + 10)),
+        });
+        let accept_msg = ExecuteMsg::UpdateOwnership(OwnershipAction::AcceptOwnership);
+        app.execute_contract(owner.clone(), staking_address.clone(), &transfer_msg, &[])
+            .unwrap();
+        let resp: OwnershipResponse = app
+            .wrap()
+            .query_wasm_smart(staking_address.clone(), &QueryMsg::GetOwnership {})
+            .unwrap();
+        assert_eq!(resp.owner, Some(owner.to_string()));
+        assert_eq!(resp.pending_owner, Some(new_owner.to_string()));
+
+        // a cancelled transfer can not be accepted
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::UpdateOwnership(OwnershipAction::CancelOwnershipTransfer),
+            &[],
+        )
+        .unwrap();
+        let err = app
+            .execute_contract(new_owner.clone(), staking_address.clone(), &accept_msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::NoPendingOwnership {}.to_string()
+        );
+
+        // neither can an expired one
+        app.execute_contract(owner.clone(), staking_address.clone(), &transfer_msg, &[])
+            .unwrap();
+        app.update_block(|block| block.height += 10);
+        let err = app
+            .execute_contract(new_owner.clone(), staking_address.clone(), &accept_msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::OwnershipTransferExpired {}.to_string()
+        );
+
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::UpdateOwnership(OwnershipAction::RenounceOwnership),
+            &[],
+        )
+        .unwrap();
+        let resp: OwnershipResponse = app
+            .wrap()
+            .query_wasm_smart(staking_address.clone(), &QueryMsg::GetOwnership {})
+            .unwrap();
+        assert_eq!(
+            resp,
+            OwnershipResponse {
+                owner: None,
+                pending_owner: None,
+                pending_expiry: None,
+            }
+        );
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                staking_address.clone(),
+                &ExecuteMsg::ChangeFee {
+                    fees: vec![coin(2, "inj")],
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::Unauthorized {}.to_string()
+        );
+    }
+
+    #[test]
+    fn test_roles() {
+        let (mut app, owner, cw721_address, staking_address) = setup();
+        let bot = Addr::unchecked("bot");
+        let whitelist_msg = ExecuteMsg::WhitelistCollection {
+            address: "collection2".to_owned(),
+            rewards: vec![native_asset(10, "inj")],
+            cycle: 604_800,
+            is_whitelisted: true,
+            spots: 1000,
+            reward_mode: None,
+        };
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::GrantRole {
+                address: bot.to_string(),
+                role: Role::CollectionManager,
+            },
+            &[],
+        )
+        .unwrap();
+        let resp: Vec<RolesResponse> = app
+            .wrap()
+            .query_wasm_smart(staking_address.clone(), &QueryMsg::GetRoles {})
+            .unwrap();
+        assert_eq!(
+            resp,
+            vec![RolesResponse {
+                address: bot.to_string(),
+                roles: vec![Role::CollectionManager],
+            }]
+        );
+        app.execute_contract(bot.clone(), staking_address.clone(), &whitelist_msg, &[])
+            .unwrap();
+        // collection managers hold no fee rights
+        let err = app
+            .execute_contract(
+                bot.clone(),
+                staking_address.clone(),
+                &ExecuteMsg::WithdrawFee {
+                    fee: coin(1, "inj"),
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::Unauthorized {}.to_string()
+        );
+        // nor can they take funds out of reward pools
+        let withdraw_msg = ExecuteMsg::WithdrawCollectionReward {
+            address: cw721_address.to_string(),
+            asset: native_asset(1_000, "inj"),
+        };
+        for msg in [
+            withdraw_msg.clone(),
+            ExecuteMsg::ReallocateCollectionReward {
+                from: cw721_address.to_string(),
+                to: "collection2".to_owned(),
+                asset: native_asset(1_000, "inj"),
+            },
+        ] {
+            let err = app
+                .execute_contract(bot.clone(), staking_address.clone(), &msg, &[])
+                .unwrap_err();
+            assert_eq!(
+                err.root_cause().to_string(),
+                ContractError::Unauthorized {}.to_string()
+            );
+        }
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::GrantRole {
+                address: bot.to_string(),
+                role: Role::FeeManager,
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(bot.clone(), staking_address.clone(), &withdraw_msg, &[])
+            .unwrap();
+        let balance = app.wrap().query_balance(bot.clone(), "inj").unwrap();
+        assert_eq!(balance.amount.u128(), 1_000);
+
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::RevokeRole {
+                address: bot.to_string(),
+                role: Role::CollectionManager,
+            },
+            &[],
+        )
+        .unwrap();
+        let err = app
+            .execute_contract(bot.clone(), staking_address.clone(), &whitelist_msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::Unauthorized {}.to_string()
+        );
+    }
+
+    #[test]
+    fn test_pause_and_emergency() {
+        let (mut app, owner, cw721_address, staking_address) = setup();
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::SetUnbondingDuration {
+                address: cw721_address.to_string(),
+                duration: 86_400,
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            owner.clone(),
+            cw721_address.clone(),
+            &cw721_base::ExecuteMsg::<Empty, Empty>::Mint {
+                token_id: "2".to_owned(),
+                owner: owner.to_string(),
+                token_uri: None,
+                extension: Empty {},
+            },
+            &[],
+        )
+        .unwrap();
+        for token_id in ["0", "2"] {
+            app.execute_contract(
+                owner.clone(),
+                cw721_address.clone(),
+                &cw721_base::ExecuteMsg::<Empty, Empty>::SendNft {
+                    contract: staking_address.to_string(),
+                    token_id: token_id.to_owned(),
+                    msg: Binary::default(),
+                },
+                &[],
+            )
+            .unwrap();
+        }
+        // the claim moves the accumulators past the 5inj accrued by token 0
+        app.update_block(|block| block.time = block.time.plus_seconds(302_400));
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::ClaimReward {
+                collection: cw721_address.to_string(),
+                token_id: "2".to_owned(),
+                recipient: None,
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::SetPaused { paused: true },
+            &[],
+        )
+        .unwrap();
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                cw721_address.clone(),
+                &cw721_base::ExecuteMsg::<Empty, Empty>::SendNft {
+                    contract: staking_address.to_string(),
+                    token_id: "1".to_owned(),
+                    msg: Binary::default(),
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::Paused {}.to_string()
+        );
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                staking_address.clone(),
+                &ExecuteMsg::Unstake {
+                    collection: cw721_address.to_string(),
+                    token_id: "0".to_owned(),
+                    recipient: None,
+                },
+                &[coin(1, "inj")],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::Paused {}.to_string()
+        );
+
+        let emergency_msg = ExecuteMsg::EmergencyUnstake {
+            collection: cw721_address.to_string(),
+            token_id: "0".to_owned(),
+        };
+        let err = app
+            .execute_contract(owner.clone(), staking_address.clone(), &emergency_msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::NotEmergency {}.to_string()
+        );
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::SetEmergency { emergency: true },
+            &[],
+        )
+        .unwrap();
+        // the nft comes back before a cycle ends, without fee or reward
+        app.execute_contract(owner.clone(), staking_address.clone(), &emergency_msg, &[])
+            .unwrap();
+        let owner_of = |app: &App, token_id: &str| {
+            let resp: cw721::OwnerOfResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    cw721_address.clone(),
+                    &cw721::Cw721QueryMsg::OwnerOf {
+                        token_id: token_id.to_owned(),
+                        include_expired: None,
+                    },
+                )
+                .unwrap();
+            resp.owner
+        };
+        assert_eq!(owner_of(&app, "0"), owner.to_string());
+        let balance = app.wrap().query_balance(owner.clone(), "inj").unwrap();
+        assert_eq!(balance.amount.u128(), 1_000_000 - 100_000 + 5);
+        // the forfeited reward no longer reserves pool funds
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                staking_address.clone(),
+                &ExecuteMsg::WithdrawCollectionReward {
+                    address: cw721_address.to_string(),
+                    asset: native_asset(100_001, "inj"),
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::PoolFundsAllocated {
+                available: Uint128::new(100_000 - 5)
+            }
+            .to_string()
+        );
+
+        // an unbonding nft goes to the recipient chosen on unstake
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::SetPaused { paused: false },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            owner.clone(),
+            cw721_address.clone(),
+            &cw721_base::ExecuteMsg::<Empty, Empty>::SendNft {
+                contract: staking_address.to_string(),
+                token_id: "1".to_owned(),
+                msg: Binary::default(),
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::Unstake {
+                collection: cw721_address.to_string(),
+                token_id: "1".to_owned(),
+                recipient: Some("vault".to_owned()),
+            },
+            &[coin(1, "inj")],
+        )
+        .unwrap();
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::EmergencyUnstake {
+                collection: cw721_address.to_string(),
+                token_id: "1".to_owned(),
+            },
+            &[],
+        )
+        .unwrap();
+        assert_eq!(owner_of(&app, "1"), "vault");
+    }
+
+    #[test]
+    fn test_unbonding() {
+        let (mut app, owner, cw721_address, staking_address) = setup();
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                staking_address.clone(),
+                &ExecuteMsg::SetUnbondingDuration {
+                    address: cw721_address.to_string(),
+                    duration: u64::MAX,
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::InvalidUnbondingDuration { max: 7_776_000 }.to_string()
+        );
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::SetUnbondingDuration {
+                address: cw721_address.to_string(),
+                duration: 86_400,
+            },
+            &[],
+        )
+        .unwrap();
+        for token_id in ["0", "1"] {
+            app.execute_contract(
+                owner.clone(),
+                cw721_address.clone(),
+                &cw721_base::ExecuteMsg::<Empty, Empty>::SendNft {
+                    contract: staking_address.to_string(),
+                    token_id: token_id.to_owned(),
+                    msg: Binary::default(),
+                },
+                &[],
+            )
+            .unwrap();
+        }
+        // stakings keep the unbonding duration they were staked with
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::SetUnbondingDuration {
+                address: cw721_address.to_string(),
+                duration: 7_776_000,
+            },
+            &[],
+        )
+        .unwrap();
+        app.update_block(|block| block.time = block.time.plus_seconds(604_800));
+        let unstake = |app: &mut App, token_id: &str| {
+            app.execute_contract(
+                owner.clone(),
+                staking_address.clone(),
+                &ExecuteMsg::Unstake {
+                    collection: cw721_address.to_string(),
+                    token_id: token_id.to_owned(),
+                    recipient: None,
+                },
+                &[],
+            )
+            .unwrap();
+            app.block_info().time.plus_seconds(86_400)
+        };
+        let unbonding = |app: &App| -> Vec<UnbondingResponse> {
+            app.wrap()
+                .query_wasm_smart(
+                    staking_address.clone(),
+                    &QueryMsg::GetUnbonding {
+                        owner: owner.to_string(),
+                    },
+                )
+                .unwrap()
+        };
+        let unbonding_response = |token_id: &str, release_timestamp| UnbondingResponse {
+            token_address: cw721_address.to_string(),
+            token_id: token_id.to_owned(),
+            release_timestamp,
+        };
+        // entries are listed by release time, still staked tokens are left out
+        let first_release = unstake(&mut app, "1");
+        assert_eq!(
+            unbonding(&app),
+            vec![unbonding_response("1", first_release)]
+        );
+        app.update_block(|block| block.time = block.time.plus_seconds(100));
+        let second_release = unstake(&mut app, "0");
+        assert_eq!(
+            unbonding(&app),
+            vec![
+                unbonding_response("1", first_release),
+                unbonding_response("0", second_release),
+            ]
+        );
+
+        // no accrual while unbonding, the reward was settled on unstake
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                staking_address.clone(),
+                &ExecuteMsg::WithdrawUnbonded {
+                    owner: None,
+                    limit: None,
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::NothingToWithdraw {}.to_string()
+        );
+        app.update_block(|block| block.time = block.time.plus_seconds(86_400));
+        // released nfts are withdrawn in batches, earliest release first
+        let withdraw_msg = ExecuteMsg::WithdrawUnbonded {
+            owner: None,
+            limit: Some(1),
+        };
+        let owner_of = |app: &App, token_id: &str| {
+            let resp: cw721::OwnerOfResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    cw721_address.clone(),
+                    &cw721::Cw721QueryMsg::OwnerOf {
+                        token_id: token_id.to_owned(),
+                        include_expired: None,
+                    },
+                )
+                .unwrap();
+            resp.owner
+        };
+        app.execute_contract(owner.clone(), staking_address.clone(), &withdraw_msg, &[])
+            .unwrap();
+        assert_eq!(owner_of(&app, "1"), owner.to_string());
+        assert_eq!(owner_of(&app, "0"), staking_address.to_string());
+        app.execute_contract(owner.clone(), staking_address.clone(), &withdraw_msg, &[])
+            .unwrap();
+        assert_eq!(owner_of(&app, "0"), owner.to_string());
+        assert!(unbonding(&app).is_empty());
+        let balance = app.wrap().query_balance(owner.clone(), "inj").unwrap();
+        assert_eq!(balance.amount.u128(), 1_000_000 - 100_000 + 20);
+    }
+
+    #[test]
+    fn test_unstake_fee_denoms() {
+        let (mut app, owner, cw721_address, staking_address) = setup();
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::SetCollectionUnstakeFees {
+                address: cw721_address.to_string(),
+                fees: Some(vec![coin(5, "inj"), coin(3, "usdt")]),
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            owner.clone(),
+            cw721_address.clone(),
+            &cw721_base::ExecuteMsg::<Empty, Empty>::SendNft {
+                contract: staking_address.to_string(),
+                token_id: "0".to_owned(),
+                msg: Binary::default(),
+            },
+            &[],
+        )
+        .unwrap();
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                staking_address.clone(),
+                &ExecuteMsg::Unstake {
+                    collection: cw721_address.to_string(),
+                    token_id: "0".to_owned(),
+                    recipient: None,
+                },
+                &[coin(2, "usdt")],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::NotEnoughUnstakeFee {}.to_string()
+        );
+        // the overpayment is refunded
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::Unstake {
+                collection: cw721_address.to_string(),
+                token_id: "0".to_owned(),
+                recipient: None,
+            },
+            &[coin(10, "usdt")],
+        )
+        .unwrap();
+        let balance = app.wrap().query_balance(owner.clone(), "usdt").unwrap();
+        assert_eq!(balance.amount.u128(), 1_000_000 - 3);
+        let resp: ConfigResponse = app
+            .wrap()
+            .query_wasm_smart(staking_address.clone(), &QueryMsg::GetConfig {})
+            .unwrap();
+        assert_eq!(resp.fees_collected, vec![coin(3, "usdt")]);
+
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                staking_address.clone(),
+                &ExecuteMsg::WithdrawFee {
+                    fee: coin(1, "inj"),
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::NotEnoughFeeCollected {}.to_string()
+        );
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::WithdrawFee {
+                fee: coin(3, "usdt"),
+            },
+            &[],
+        )
+        .unwrap();
+        let balance = app.wrap().query_balance(owner.clone(), "usdt").unwrap();
+        assert_eq!(balance.amount.u128(), 1_000_000);
+    }
+
+    #[test]
+    fn test_unstake_penalty() {
+        let (mut app, owner, cw721_address, staking_address) = setup();
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::SetCollectionUnstakeFees {
+                address: cw721_address.to_string(),
+                fees: Some(vec![coin(100, "inj")]),
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::SetUnstakePenalty {
+                address: cw721_address.to_string(),
+                curve: PenaltyCurve::Linear,
+                charge: PenaltyCharge::Coin,
+            },
+            &[],
+        )
+        .unwrap();
+        for token_id in ["0", "1"] {
+            app.execute_contract(
+                owner.clone(),
+                cw721_address.clone(),
+                &cw721_base::ExecuteMsg::<Empty, Empty>::SendNft {
+                    contract: staking_address.to_string(),
+                    token_id: token_id.to_owned(),
+                    msg: Binary::default(),
+                },
+                &[],
+            )
+            .unwrap();
+        }
+        // half way through the cycle half of the fee is due
+        app.update_block(|block| block.time = block.time.plus_seconds(302_400));
+        let resp: UnstakeFeeResponse = app
+            .wrap()
+            .query_wasm_smart(
+                staking_address.clone(),
+                &QueryMsg::GetUnstakeFee {
+                    collection: cw721_address.to_string(),
+                    token_id: "0".to_owned(),
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            resp,
+            UnstakeFeeResponse {
+                fees: vec![coin(50, "inj")],
+                reward_share: Decimal::zero(),
+                withheld_rewards: vec![],
+            }
+        );
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::Unstake {
+                collection: cw721_address.to_string(),
+                token_id: "0".to_owned(),
+                recipient: None,
+            },
+            &[coin(50, "inj")],
+        )
+        .unwrap();
+
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                staking_address.clone(),
+                &ExecuteMsg::SetUnstakePenalty {
+                    address: cw721_address.to_string(),
+                    curve: PenaltyCurve::Stepwise {
+                        steps: vec![
+                            PenaltyStep {
+                                elapsed: 86_400,
+                                percent: Decimal::percent(50),
+                            },
+                            PenaltyStep {
+                                elapsed: 0,
+                                percent: Decimal::one(),
+                            },
+                        ],
+                    },
+                    charge: PenaltyCharge::Coin,
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::InvalidPenalty {}.to_string()
+        );
+        // a share of the reward is withheld instead of an attached fee
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::SetUnstakePenalty {
+                address: cw721_address.to_string(),
+                curve: PenaltyCurve::Stepwise {
+                    steps: vec![PenaltyStep {
+                        elapsed: 86_400,
+                        percent: Decimal::percent(50),
+                    }],
+                },
+                charge: PenaltyCharge::RewardShare {
+                    percent: Decimal::percent(40),
+                },
+            },
+            &[],
+        )
+        .unwrap();
+        let resp: UnstakeFeeResponse = app
+            .wrap()
+            .query_wasm_smart(
+                staking_address.clone(),
+                &QueryMsg::GetUnstakeFee {
+                    collection: cw721_address.to_string(),
+                    token_id: "1".to_owned(),
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            resp,
+            UnstakeFeeResponse {
+                fees: vec![],
+                reward_share: Decimal::percent(20),
+                withheld_rewards: vec![native_asset(1, "inj")],
+            }
+        );
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::Unstake {
+                collection: cw721_address.to_string(),
+                token_id: "1".to_owned(),
+                recipient: None,
+            },
+            &[],
+        )
+        .unwrap();
+        let resp: ConfigResponse = app
+            .wrap()
+            .query_wasm_smart(staking_address.clone(), &QueryMsg::GetConfig {})
+            .unwrap();
+        assert_eq!(resp.fees_collected, vec![coin(51, "inj")]);
+        let balance = app.wrap().query_balance(owner.clone(), "inj").unwrap();
+        assert_eq!(balance.amount.u128(), 1_000_000 - 100_000 - 50 + 5 + 4);
+
+        // claiming before unstaking does not avoid the withheld share
+        app.execute_contract(
+            owner.clone(),
+            cw721_address.clone(),
+            &cw721_base::ExecuteMsg::<Empty, Empty>::SendNft {
+                contract: staking_address.to_string(),
+                token_id: "1".to_owned(),
+                msg: Binary::default(),
+            },
+            &[],
+        )
+        .unwrap();
+        app.update_block(|block| block.time = block.time.plus_seconds(302_400));
+        for msg in [
+            ExecuteMsg::ClaimReward {
+                collection: cw721_address.to_string(),
+                token_id: "1".to_owned(),
+                recipient: None,
+            },
+            ExecuteMsg::Unstake {
+                collection: cw721_address.to_string(),
+                token_id: "1".to_owned(),
+                recipient: None,
+            },
+        ] {
+            app.execute_contract(owner.clone(), staking_address.clone(), &msg, &[])
+                .unwrap();
+        }
+        let resp: ConfigResponse = app
+            .wrap()
+            .query_wasm_smart(staking_address.clone(), &QueryMsg::GetConfig {})
+            .unwrap();
+        assert_eq!(resp.fees_collected, vec![coin(52, "inj")]);
+        let balance = app.wrap().query_balance(owner.clone(), "inj").unwrap();
+        assert_eq!(balance.amount.u128(), 1_000_000 - 100_000 - 50 + 5 + 4 + 4);
+    }
+
+    #[test]
+    fn test_fee_distribution() {
+        let (mut app, owner, cw721_address, staking_address) = setup();
+        let treasury = Addr::unchecked("treasury");
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::SetCollectionUnstakeFees {
+                address: cw721_address.to_string(),
+                fees: Some(vec![coin(100, "inj")]),
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            owner.clone(),
+            cw721_address.clone(),
+            &cw721_base::ExecuteMsg::<Empty, Empty>::SendNft {
+                contract: staking_address.to_string(),
+                token_id: "0".to_owned(),
+                msg: Binary::default(),
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::Unstake {
+                collection: cw721_address.to_string(),
+                token_id: "0".to_owned(),
+                recipient: None,
+            },
+            &[coin(100, "inj")],
+        )
+        .unwrap();
+
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                staking_address.clone(),
+                &ExecuteMsg::SetFeeDistribution {
+                    shares: vec![FeeShare {
+                        recipient: FeeRecipient::Address {
+                            address: treasury.to_string(),
+                        },
+                        bps: 9_000,
+                    }],
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::InvalidFeeShares {}.to_string()
+        );
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::SetFeeDistribution {
+                shares: vec![
+                    FeeShare {
+                        recipient: FeeRecipient::Address {
+                            address: treasury.to_string(),
+                        },
+                        bps: 5_000,
+                    },
+                    FeeShare {
+                        recipient: FeeRecipient::CollectionCreator {
+                            collection: cw721_address.to_string(),
+                        },
+                        bps: 2_000,
+                    },
+                    FeeShare {
+                        recipient: FeeRecipient::RewardPool {
+                            collection: cw721_address.to_string(),
+                        },
+                        bps: 3_000,
+                    },
+                ],
+            },
+            &[],
+        )
+        .unwrap();
+        // anyone can distribute, the creator share of a collection without admin stays
+        app.execute_contract(
+            Addr::unchecked("anyone"),
+            staking_address.clone(),
+            &ExecuteMsg::DistributeFees {},
+            &[],
+        )
+        .unwrap();
+        let balance = app.wrap().query_balance(treasury, "inj").unwrap();
+        assert_eq!(balance.amount.u128(), 50);
+        let resp: ConfigResponse = app
+            .wrap()
+            .query_wasm_smart(staking_address.clone(), &QueryMsg::GetConfig {})
+            .unwrap();
+        assert_eq!(resp.fees_collected, vec![coin(20, "inj")]);
+        let resp: Vec<CollectionResponse> = app
+            .wrap()
+            .query_wasm_smart(staking_address.clone(), &QueryMsg::GetCollections {})
+            .unwrap();
+        assert_eq!(resp[0].rewards[0].pool_amount, Uint128::new(100_030));
+    }
+
+    #[test]
+    fn test_fee_recycling() {
+        let (mut app, owner, cw721_address, staking_address) = setup();
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::SetCollectionUnstakeFees {
+                address: cw721_address.to_string(),
+                fees: Some(vec![coin(100, "inj"), coin(100, "usdt")]),
+            },
+            &[],
+        )
+        .unwrap();
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                staking_address.clone(),
+                &ExecuteMsg::SetFeeRecycling {
+                    address: cw721_address.to_string(),
+                    share: Decimal::percent(150),
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::InvalidRecycleShare {}.to_string()
+        );
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::SetFeeRecycling {
+                address: cw721_address.to_string(),
+                share: Decimal::percent(60),
+            },
+            &[],
+        )
+        .unwrap();
+        for token_id in ["0", "1"] {
+            app.execute_contract(
+                owner.clone(),
+                cw721_address.clone(),
+                &cw721_base::ExecuteMsg::<Empty, Empty>::SendNft {
+                    contract: staking_address.to_string(),
+                    token_id: token_id.to_owned(),
+                    msg: Binary::default(),
+                },
+                &[],
+            )
+            .unwrap();
+        }
+        // only fees in a denom the pool rewards are recycled
+        for (token_id, fee) in [("0", coin(100, "inj")), ("1", coin(100, "usdt"))] {
+            app.execute_contract(
+                owner.clone(),
+                staking_address.clone(),
+                &ExecuteMsg::Unstake {
+                    collection: cw721_address.to_string(),
+                    token_id: token_id.to_owned(),
+                    recipient: None,
+                },
+                &[fee],
+            )
+            .unwrap();
+        }
+        let resp: ConfigResponse = app
+            .wrap()
+            .query_wasm_smart(staking_address.clone(), &QueryMsg::GetConfig {})
+            .unwrap();
+        assert_eq!(
+            resp.fees_collected,
+            vec![coin(40, "inj"), coin(100, "usdt")]
+        );
+        let resp: Vec<CollectionResponse> = app
+            .wrap()
+            .query_wasm_smart(staking_address.clone(), &QueryMsg::GetCollections {})
+            .unwrap();
+        assert_eq!(resp[0].rewards[0].pool_amount, Uint128::new(100_060));
+    }
+
+    #[test]
+    fn test_operators() {
+        let (mut app, owner, cw721_address, staking_address) = setup();
+        let bot = Addr::unchecked("bot");
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::SetUnbondingDuration {
+                address: cw721_address.to_string(),
+                duration: 86_400,
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            owner.clone(),
+            cw721_address.clone(),
+            &cw721_base::ExecuteMsg::<Empty, Empty>::SendNft {
+                contract: staking_address.to_string(),
+                token_id: "0".to_owned(),
+                msg: Binary::default(),
+            },
+            &[],
+        )
+        .unwrap();
+        let claim_msg = |recipient: Option<String>| ExecuteMsg::ClaimReward {
+            collection: cw721_address.to_string(),
+            token_id: "0".to_owned(),
+            recipient,
+        };
+        let err = app
+            .execute_contract(bot.clone(), staking_address.clone(), &claim_msg(None), &[])
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::Unauthorized {}.to_string()
+        );
+        let expiry = Expiration::AtTime(app.block_info().time.plus_seconds(1_209_600));
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::ApproveOperator {
+                operator: bot.to_string(),
+                claim: true,
+                unstake: false,
+                set_recipient: false,
+                expiry: Some(expiry),
+            },
+            &[],
+        )
+        .unwrap();
+        let resp: Vec<OperatorResponse> = app
+            .wrap()
+            .query_wasm_smart(
+                staking_address.clone(),
+                &QueryMsg::GetOperators {
+                    owner: owner.to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            resp,
+            vec![OperatorResponse {
+                operator: bot.to_string(),
+                approval: OperatorApproval {
+                    claim: true,
+                    unstake: false,
+                    set_recipient: false,
+                    expiry: Some(expiry),
+                },
+            }]
+        );
+        app.update_block(|block| block.time = block.time.plus_seconds(604_800));
+        // the operator can not redirect the reward without a recipient override
+        let err = app
+            .execute_contract(
+                bot.clone(),
+                staking_address.clone(),
+                &claim_msg(Some("vault".to_owned())),
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::Unauthorized {}.to_string()
+        );
+        app.execute_contract(bot.clone(), staking_address.clone(), &claim_msg(None), &[])
+            .unwrap();
+        let balance = app.wrap().query_balance(owner.clone(), "inj").unwrap();
+        assert_eq!(balance.amount.u128(), 1_000_000 - 100_000 + 10);
+        let err = app
+            .execute_contract(
+                bot.clone(),
+                staking_address.clone(),
+                &ExecuteMsg::Unstake {
+                    collection: cw721_address.to_string(),
+                    token_id: "0".to_owned(),
+                    recipient: None,
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::Unauthorized {}.to_string()
+        );
+
+        app.update_block(|block| block.time = block.time.plus_seconds(604_800));
+        let err = app
+            .execute_contract(bot.clone(), staking_address.clone(), &claim_msg(None), &[])
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::Unauthorized {}.to_string()
+        );
+
+        // an unbonding nft goes to the recipient chosen by the operator
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::ApproveOperator {
+                operator: bot.to_string(),
+                claim: true,
+                unstake: true,
+                set_recipient: true,
+                expiry: None,
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            bot.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::Unstake {
+                collection: cw721_address.to_string(),
+                token_id: "0".to_owned(),
+                recipient: Some("vault".to_owned()),
+            },
+            &[],
+        )
+        .unwrap();
+        app.update_block(|block| block.time = block.time.plus_seconds(86_400));
+        let withdraw_msg = ExecuteMsg::WithdrawUnbonded {
+            owner: Some(owner.to_string()),
+            limit: None,
+        };
+        let err = app
+            .execute_contract(
+                Addr::unchecked("other"),
+                staking_address.clone(),
+                &withdraw_msg,
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::Unauthorized {}.to_string()
+        );
+        app.execute_contract(bot.clone(), staking_address.clone(), &withdraw_msg, &[])
+            .unwrap();
+        let resp: cw721::OwnerOfResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw721_address.clone(),
+                &cw721::Cw721QueryMsg::OwnerOf {
+                    token_id: "0".to_owned(),
+                    include_expired: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(resp.owner, "vault");
+    }
+
+    #[test]
+    fn test_multiple_reward_denoms() {
+        let (mut app, owner, cw721_address, staking_address) = setup();
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::WhitelistCollection {
+                address: cw721_address.to_string(),
+                rewards: vec![native_asset(10, "inj"), native_asset(20, "usdt")],
+                cycle: 604_800,
+                is_whitelisted: true,
+                spots: 1000,
+                reward_mode: None,
+            },
+            &[],
+        )
+        .unwrap();
+        // unknown denoms cannot be deposited
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                staking_address.clone(),
+                &ExecuteMsg::DepositCollectionReward {
+                    address: cw721_address.to_string(),
+                },
+                &[coin(100, "usdt"), coin(100, "usdc")],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::UnknownRewardAsset {
+                asset: "usdc".to_owned()
+            }
+            .to_string()
+        );
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::DepositCollectionReward {
+                address: cw721_address.to_string(),
+            },
+            &[coin(100_000, "usdt")],
+        )
+        .unwrap();
+
+        app.execute_contract(
+            owner.clone(),
+            cw721_address.clone(),
+            &cw721_base::ExecuteMsg::<Empty, Empty>::SendNft {
+                contract: staking_address.to_string(),
+                token_id: "0".to_owned(),
+                msg: to_json_binary(&StakeMsg::default()).unwrap(),
+            },
+            &[],
+        )
+        .unwrap();
+        app.update_block(|block| block.time = block.time.plus_seconds(604_800));
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::ClaimReward {
+                collection: cw721_address.to_string(),
+                token_id: "0".to_owned(),
+                recipient: None,
+            },
+            &[],
+        )
+        .unwrap();
+        let balances = app.wrap().query_all_balances(owner.clone()).unwrap();
+        assert_eq!(
+            balances,
+            vec![
+                coin(1_000_000 - 100_000 + 10, "inj"),
+                coin(1_000_000, "usdc"),
+                coin(1_000_000 - 100_000 + 20, "usdt")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reward_liability() {
+        let (mut app, owner, cw721_address, staking_address) = setup();
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::WhitelistCollection {
+                address: cw721_address.to_string(),
+                rewards: vec![native_asset(10, "inj")],
+                cycle: 604_800,
+                is_whitelisted: true,
+                spots: 1000,
+                reward_mode: Some(RewardMode::PerCycle),
+            },
+            &[],
+        )
+        .unwrap();
+        for token_id in ["0", "1"] {
+            app.execute_contract(
+                owner.clone(),
+                cw721_address.clone(),
+                &cw721_base::ExecuteMsg::<Empty, Empty>::SendNft {
+                    contract: staking_address.to_string(),
+                    token_id: token_id.to_owned(),
+                    msg: Binary::default(),
+                },
+                &[],
+            )
+            .unwrap();
+        }
+        app.update_block(|block| block.time = block.time.plus_seconds(907_200));
+        let available = |app: &mut App| {
+            let err = app
+                .execute_contract(
+                    owner.clone(),
+                    staking_address.clone(),
+                    &ExecuteMsg::WithdrawCollectionReward {
+                        address: cw721_address.to_string(),
+                        asset: native_asset(100_000, "inj"),
+                    },
+                    &[],
+                )
+                .unwrap_err();
+            err.root_cause().to_string()
+        };
+        // the partial cycle stays reserved until the stakings end
+        assert_eq!(
+            available(&mut app),
+            ContractError::PoolFundsAllocated {
+                available: Uint128::new(99_970)
+            }
+            .to_string()
+        );
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::ClaimReward {
+                collection: cw721_address.to_string(),
+                token_id: "0".to_owned(),
+                recipient: None,
+            },
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            available(&mut app),
+            ContractError::PoolFundsAllocated {
+                available: Uint128::new(99_970)
+            }
+            .to_string()
+        );
+        // unstaking pays the completed cycle and releases the forfeited partial one
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::Unstake {
+                collection: cw721_address.to_string(),
+                token_id: "1".to_owned(),
+                recipient: None,
+            },
+            &[coin(1, "inj")],
+        )
+        .unwrap();
+        assert_eq!(
+            available(&mut app),
+            ContractError::PoolFundsAllocated {
+                available: Uint128::new(99_975)
+            }
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn test_cw20_rewards() {
+        let (mut app, owner, cw721_address, staking_address) = setup();
+        let cw20_code_id = app.store_code(cw20_base_contract());
+        let cw20_address = app
+            .instantiate_contract(
+                cw20_code_id,
+                owner.clone(),
+                &cw20_base::msg::InstantiateMsg {
+                    name: "Reward Token".to_owned(),
+                    symbol: "RWD".to_owned(),
+                    decimals: 6,
+                    initial_balances: vec![cw20::Cw20Coin {
+                        address: owner.to_string(),
+                        amount: Uint128::new(1_000_000),
+                    }],
+                    mint: None,
+                    marketing: None,
+                },
+                &[],
+                "deploy cw20_base contract",
+                None,
+            )
+            .unwrap();
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::WhitelistCollection {
+                address: cw721_address.to_string(),
+                rewards: vec![Asset {
+                    info: AssetInfo::Cw20 {
+                        contract_addr: cw20_address.to_string(),
+                    },
+                    amount: Uint128::new(30),
+                }],
+                cycle: 604_800,
+                is_whitelisted: true,
+                spots: 1000,
+                reward_mode: None,
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            owner.clone(),
+            cw20_address.clone(),
+            &cw20::Cw20ExecuteMsg::Send {
+                contract: staking_address.to_string(),
+                amount: Uint128::new(100_000),
+                msg: to_json_binary(&ReceiveMsg::DepositCollectionReward {
+                    address: cw721_address.to_string(),
+                })
+                .unwrap(),
+            },
+            &[],
+        )
+        .unwrap();
+        let resp: Vec<CollectionResponse> = app
+            .wrap()
+            .query_wasm_smart(staking_address.clone(), &QueryMsg::GetCollections {})
+            .unwrap();
+        assert_eq!(resp[0].rewards[1].pool_amount, Uint128::new(100_000));
+
+        app.execute_contract(
+            owner.clone(),
+            cw721_address.clone(),
+            &cw721_base::ExecuteMsg::<Empty, Empty>::SendNft {
+                contract: staking_address.to_string(),
+                token_id: "0".to_owned(),
+                msg: to_json_binary(&StakeMsg::default()).unwrap(),
+            },
+            &[],
+        )
+        .unwrap();
+        app.update_block(|block| block.time = block.time.plus_seconds(604_800));
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::ClaimReward {
+                collection: cw721_address.to_string(),
+                token_id: "0".to_owned(),
+                recipient: None,
+            },
+            &[],
+        )
+        .unwrap();
+        let resp: cw20::BalanceResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw20_address,
+                &cw20::Cw20QueryMsg::Balance {
+                    address: owner.to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(resp.balance, Uint128::new(1_000_000 - 100_000 + 30));
+    }
+
+    #[test]
+    fn test_migration() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let now = env.block.time;
+        // neither other contracts nor newer versions can be migrated
+        for (contract, version) in [
+            ("Other Staking", "0.0.1"),
+            ("Injective CW721 Staking", "9.0.0"),
+        ] {
+            cw2::set_contract_version(&mut deps.storage, contract, version).unwrap();
+            let err = migrate(deps.as_mut(), env.clone(), MigrateMsg { limit: None }).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                ContractError::CannotMigrate {
+                    previous_contract: contract.to_owned(),
+                    previous_version: version.to_owned(),
+                }
+                .to_string()
+            );
+        }
+        cw2::set_contract_version(&mut deps.storage, "Injective CW721 Staking", "0.0.1").unwrap();
+        LEGACY_CONFIG
+            .save(
+                &mut deps.storage,
+                &LegacyConfig {
+                    owner: "owner".to_owned(),
+                    unstake_fee: coin(1, "inj"),
+                    fee_collected: 7,
+                },
+            )
+            .unwrap();
+        for (address, spots, pool_amount) in [("nft", 2, 5), ("unlimited", 0, 100)] {
+            LEGACY_COLLECTIONS
+                .save(
+                    &mut deps.storage,
+                    address.to_owned(),
+                    &LegacyCollection {
+                        reward: coin(10, "inj"),
+                        cycle: 100,
+                        is_whitelisted: true,
+                        spots,
+                        pool_amount,
+                    },
+                )
+                .unwrap();
+        }
+        let legacy_staking = |token_address: &str, token_id: &str, end_timestamp| LegacyStaking {
+            token_address: token_address.to_owned(),
+            token_id: token_id.to_owned(),
+            start_timestamp: now.minus_seconds(200),
+            end_timestamp,
+            is_paid: false,
+        };
+        LEGACY_STAKINGS
+            .save(
+                &mut deps.storage,
+                "alice".to_owned(),
+                &vec![
+                    legacy_staking("nft", "1", Timestamp::from_nanos(0)),
+                    legacy_staking("unlimited", "2", Timestamp::from_nanos(0)),
+                    legacy_staking("nft", "3", now.minus_seconds(100)),
+                ],
+            )
+            .unwrap();
+        LEGACY_STAKINGS
+            .save(
+                &mut deps.storage,
+                "bob".to_owned(),
+                &vec![legacy_staking("nft", "4", Timestamp::from_nanos(0))],
+            )
+            .unwrap();
+
+        // the first batch only converts part of the first owner
+        migrate(deps.as_mut(), env.clone(), MigrateMsg { limit: Some(2) }).unwrap();
+        assert_eq!(
+            cw2::get_contract_version(&deps.storage).unwrap().version,
+            "0.1.0"
+        );
+        assert_eq!(
+            LEGACY_STAKINGS
+                .load(&deps.storage, "alice".to_owned())
+                .unwrap()
+                .len(),
+            1
+        );
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.owner, Some("owner".to_owned()));
+        assert_eq!(config.unstake_fees, vec![coin(1, "inj")]);
+        assert_eq!(
+            FEES_COLLECTED
+                .load(&deps.storage, "inj".to_owned())
+                .unwrap(),
+            Uint128::new(7)
+        );
+
+        // legacy positions are not counted yet, so staking waits for the migration
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("nft", &[]),
+            ExecuteMsg::ReceiveNft(UniversalNftReceiveMsg {
+                sender: "carol".to_owned(),
+                token_id: "5".to_owned(),
+                msg: Binary::default(),
+                edition: None,
+            }),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            ContractError::MigrationPending {}.to_string()
+        );
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::MigrateStakings { limit: Some(10) },
+        )
+        .unwrap();
+        assert!(LEGACY_STAKINGS.is_empty(&deps.storage));
+        // the pool only covers half of the unpaid legacy reward
+        assert_eq!(res.messages.len(), 1);
+
+        for (token_address, token_id, owner) in [
+            ("nft", "1", "alice"),
+            ("unlimited", "2", "alice"),
+            ("nft", "4", "bob"),
+        ] {
+            let staking = stakings()
+                .load(
+                    &deps.storage,
+                    (token_address.to_owned(), token_id.to_owned()),
+                )
+                .unwrap();
+            assert_eq!(staking.owner, owner);
+            assert_eq!(staking.start_timestamp, now.minus_seconds(200));
+        }
+        assert!(!stakings().has(&deps.storage, ("nft".to_owned(), "3".to_owned())));
+
+        let collection = COLLECTIONS.load(&deps.storage, "nft".to_owned()).unwrap();
+        assert_eq!(collection.staked, 2);
+        assert_eq!(collection.available_spots(), 0);
+        assert_eq!(collection.rewards[0].pool_amount, Uint128::zero());
+        assert_eq!(collection.rewards[0].debt_amount, Uint128::new(5));
+        assert_eq!(
+            UNPAID_REWARDS
+                .load(
+                    &deps.storage,
+                    ("nft".to_owned(), "inj".to_owned(), "alice".to_owned())
+                )
+                .unwrap(),
+            native_asset(5, "inj")
+        );
+        let collection = COLLECTIONS
+            .load(&deps.storage, "unlimited".to_owned())
+            .unwrap();
+        assert_eq!(collection.spots, u64::MAX);
+        assert_eq!(collection.staked, 1);
+    }
+
+    fn native_asset(amount: u128, denom: &str) -> Asset {
+        Asset {
+            info: AssetInfo::Native {
+                denom: denom.to_owned(),
+            },
+            amount: Uint128::new(amount),
+        }
+    }
+
+    // deploys a cw721 with two minted tokens and a staking contract whitelisting it,
+    // with a 10inj weekly reward and a 100_000inj reward pool
+    fn setup() -> (App, Addr, Addr, Addr) {
+        let owner: Addr = Addr::unchecked("owner");
+        let mut app = App::new(|router, _, storage| {
+            router
+                .bank
+                .init_balance(
+                    storage,
+                    &owner,
+                    vec![
+                        coin(1_000_000, "inj"),
+                        coin(1_000_000, "usdc"),
+                        coin(1_000_000, "usdt"),
+                    ],
+                )
+                .unwrap()
+        });
+        let cw721_base_code_id = app.store_code(cw721_base_contract());
+        let cw721_address = app
+            .instantiate_contract(
+                cw721_base_code_id,
+                owner.clone(),
+                &cw721_base::InstantiateMsg {
+                    name: "CW721 Base".to_owned(),
+                    symbol: "CWB".to_owned(),
+                    minter: owner.to_string(),
+                },
+                &[],
+                "deploy cw721_base contract",
+                None,
+            )
+            .unwrap();
+        for token_id in ["0", "1"] {
+            app.execute_contract(
+                owner.clone(),
+                cw721_address.clone(),
+                &cw721_base::ExecuteMsg::<Empty, Empty>::Mint {
+                    token_id: token_id.to_owned(),
+                    owner: owner.to_string(),
+                    token_uri: Some("token_uri".to_owned()),
+                    extension: Empty {},
+                },
+                &[],
+            )
+            .unwrap();
+        }
+        let staking_code_id = app.store_code(staking_contract());
+        let staking_address = app
+            .instantiate_contract(
+                staking_code_id,
+                owner.clone(),
+                &InstantiateMsg {
+                    unstake_fees: vec![coin(1, "inj")],
+                },
+                &[],
+                "deploy staking contract",
+                None,
+            )
+            .unwrap();
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::WhitelistCollection {
+                address: cw721_address.to_string(),
+                rewards: vec![native_asset(10, "inj")],
+                cycle: 604_800,
+                is_whitelisted: true,
+                spots: 1000,
+                reward_mode: None,
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            owner.clone(),
+            staking_address.clone(),
+            &ExecuteMsg::DepositCollectionReward {
+                address: cw721_address.to_string(),
+            },
+            &[coin(100_000, "inj")],
+        )
+        .unwrap();
+        (app, owner, cw721_address, staking_address)
+    }
+
+    fn staking_contract() -> Box<dyn Contract<Empty>> {
+        let contract = ContractWrapper::new(execute, instantiate, query);
+        Box::new(contract)
+    }
+
+    fn cw20_base_contract() -> Box<dyn Contract<Empty>> {
+        let contract = ContractWrapper::new(
+            cw20_base::contract::execute,
+            cw20_base::contract::instantiate,
+            cw20_base::contract::query,
+        );
         Box::new(contract)
     }
 